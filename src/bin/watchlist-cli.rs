@@ -0,0 +1,365 @@
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand};
+use std::path::{Path, PathBuf};
+use watchlist::{
+    config::{self, Config},
+    database::Repository,
+    formats::{self, Format},
+    http,
+    import::{self, Change, Resolution},
+    model::{ShowRecord, ShowType, Status},
+    refresh, sites,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "watchlist-cli",
+    version,
+    about = "Keep your watchlist up to date from a terminal"
+)]
+struct Cli {
+    /// Path to the database, takes priority over WATCHLIST_DB and the config file
+    #[arg(long, global = true)]
+    database: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the shows of the watchlist
+    List {
+        /// watch-later (or planned), watching, completed or dropped
+        #[arg(long)]
+        status: Option<Status>,
+        /// serial, film, cartoon or anime
+        #[arg(long = "type")]
+        show_type: Option<ShowType>,
+    },
+    /// Import a show from the link to its page
+    Add {
+        link: String,
+        #[arg(long, default_value = "watch-later")]
+        status: Status,
+    },
+    /// Mark the next episode, or the given one, as watched
+    Watched {
+        title: String,
+        #[arg(long)]
+        episode: Option<i32>,
+        #[arg(long)]
+        season: Option<i32>,
+    },
+    /// Print the shows being watched that have new episodes available
+    Next,
+    /// Remove a show from the watchlist
+    Remove { title: String },
+    /// Export the watchlist to JSON, CSV or MyAnimeList XML
+    Export {
+        /// Where to write, the standard output if omitted
+        path: Option<PathBuf>,
+        /// json, csv or mal, guessed from the file extension by default
+        #[arg(long)]
+        format: Option<Format>,
+        /// Embed pictures in base64, JSON only
+        #[arg(long)]
+        pictures: bool,
+    },
+    /// Export the upcoming episodes of the shows being watched to an iCalendar file
+    Calendar {
+        /// Where to write, the standard output if omitted
+        path: Option<PathBuf>,
+        /// Write an event for every episode instead of one weekly event per show
+        #[arg(long)]
+        expanded: bool,
+    },
+    /// Merge a file exported from the watchlist, MyAnimeList, Letterboxd, IMDb or Trakt
+    Import {
+        /// A file, or the directory of a Trakt backup
+        path: PathBuf,
+        /// json, csv, mal, letterboxd, imdb or trakt, guessed from the file by default
+        #[arg(long)]
+        format: Option<Format>,
+        /// What to do with conflicting shows: keep-local, take-incoming or keep-newer-progress
+        #[arg(long, default_value = "keep-local")]
+        resolve: Resolution,
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Update shows from the pages they were imported from, keeping fields edited by hand
+    Refresh {
+        /// The show to refresh, every show being watched by default
+        title: Option<String>,
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = Config::load()?;
+    let repository = Repository::open(&config::database_path(cli.database, &config)?)?;
+
+    match cli.command {
+        Command::List { status, show_type } => list(&repository, status, show_type),
+        Command::Add { link, status } => add(&repository, &config, &link, status),
+        Command::Watched {
+            title,
+            episode,
+            season,
+        } => watched(&repository, &title, season, episode),
+        Command::Next => next(&repository),
+        Command::Remove { title } => remove(&repository, &title),
+        Command::Export {
+            path,
+            format,
+            pictures,
+        } => export(&repository, path, format, pictures),
+        Command::Calendar { path, expanded } => calendar(&repository, path, expanded),
+        Command::Import {
+            path,
+            format,
+            resolve,
+            dry_run,
+        } => import(&repository, &path, format, resolve, dry_run),
+        Command::Refresh { title, dry_run } => {
+            refresh(&repository, &config, title.as_deref(), dry_run)
+        }
+    }
+}
+
+fn print_show(show: &ShowRecord) {
+    let episodes_count = if show.episodes_count > 0 {
+        show.episodes_count.to_string()
+    } else {
+        "?".to_owned()
+    };
+    println!(
+        "{:>4}  {:<11}  {:<7}  S{}E{}/{}  {}",
+        show.id, show.status, show.show_type, show.season, show.episode, episodes_count, show.title
+    );
+}
+
+fn find_show(repository: &Repository, title: &str) -> Result<ShowRecord> {
+    match repository
+        .load_shows()?
+        .into_iter()
+        .find(|show| show.is_titled(title))
+    {
+        Some(show) => Ok(show),
+        None => bail!("There is no show titled \"{}\"", title),
+    }
+}
+
+fn list(
+    repository: &Repository,
+    status: Option<Status>,
+    show_type: Option<ShowType>,
+) -> Result<()> {
+    for show in repository.load_shows()? {
+        if status.is_some_and(|s| s != show.status)
+            || show_type.is_some_and(|t| t != show.show_type)
+        {
+            continue;
+        }
+        print_show(&show);
+    }
+    Ok(())
+}
+
+fn add(repository: &Repository, config: &Config, link: &str, status: Status) -> Result<()> {
+    let imported = sites::fetch(link, config)?;
+    let details = imported.details();
+    let mut show = imported.show;
+    if repository
+        .load_shows()?
+        .iter()
+        .any(|s| s.is_titled(&show.title))
+    {
+        bail!("\"{}\" is already in the watchlist", show.title);
+    }
+
+    // The show is kept when its picture fails to download.
+    let picture_url = std::mem::take(&mut show.link_to_picture);
+    show.status = status;
+    show.season = 1;
+
+    show.id = repository.save_show(&show)?;
+    if picture_url.starts_with("http") {
+        let saved = http::get_bytes(&picture_url).and_then(|content| {
+            repository.update_picture(show.id, &content)?;
+            repository.update_picture_url(show.id, &picture_url)
+        });
+        if let Err(e) = saved {
+            eprintln!("Warning: failed to save the picture: {}", e);
+        }
+    }
+    print_show(&show);
+    if !details.is_empty() {
+        println!("      {}", details);
+    }
+    Ok(())
+}
+
+fn watched(
+    repository: &Repository,
+    title: &str,
+    season: Option<i32>,
+    episode: Option<i32>,
+) -> Result<()> {
+    let mut show = find_show(repository, title)?;
+    let next_season = season.unwrap_or(show.season.max(1));
+    let next_episode = match episode {
+        Some(episode) => episode,
+        None if next_season != show.season => 1,
+        None => show.episode + 1,
+    };
+    if next_season < 1 || next_episode < 0 {
+        bail!("Season must be positive and episode must not be negative");
+    }
+
+    repository.transaction(|| {
+        repository.update_progress(show.id, next_season, next_episode)?;
+        show.season = next_season;
+        show.episode = next_episode;
+
+        // Same as in the show window: watching a show starts it, the last episode finishes it.
+        if show.status == Status::WatchLater {
+            show.status = Status::Watching;
+        }
+        if show.status == Status::Watching
+            && show.episodes_count > 0
+            && show.episode >= show.episodes_count
+        {
+            show.status = Status::Completed;
+        }
+        repository.update_status(show.id, show.status)
+    })?;
+
+    print_show(&show);
+    Ok(())
+}
+
+fn next(repository: &Repository) -> Result<()> {
+    for show in repository.load_shows()? {
+        if show.status == Status::Watching && show.new_episodes_available() {
+            print_show(&show);
+        }
+    }
+    Ok(())
+}
+
+fn remove(repository: &Repository, title: &str) -> Result<()> {
+    let show = find_show(repository, title)?;
+    repository.delete_show(show.id)?;
+    println!("Removed \"{}\"", show.title);
+    Ok(())
+}
+
+fn export(
+    repository: &Repository,
+    path: Option<PathBuf>,
+    format: Option<Format>,
+    include_pictures: bool,
+) -> Result<()> {
+    let format = match (format, &path) {
+        (Some(format), _) => format,
+        (None, Some(path)) => Format::from_path(path)?,
+        (None, None) => Format::Json,
+    };
+    let content = formats::export(repository, format, include_pictures)?;
+    match path {
+        Some(path) => std::fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display())),
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+fn calendar(repository: &Repository, path: Option<PathBuf>, expanded: bool) -> Result<()> {
+    match path {
+        Some(path) => formats::export_calendar_to_file(repository, &path, expanded),
+        None => {
+            print!("{}", formats::export_calendar(repository, expanded)?);
+            Ok(())
+        }
+    }
+}
+
+fn import(
+    repository: &Repository,
+    path: &Path,
+    format: Option<Format>,
+    resolution: Resolution,
+    dry_run: bool,
+) -> Result<()> {
+    let parsed = formats::read(path, format)?;
+    for entry in &parsed.unmatched {
+        eprintln!("Not imported: {}", entry);
+    }
+    let mut items = import::preview(repository, parsed.shows)?;
+
+    for item in &mut items {
+        item.resolution = resolution;
+        let details = match item.change {
+            Change::Changed => format!("  {}", item.changed_fields().join(", ")),
+            Change::Conflict => format!("  {} ({})", item.conflicts.join(", "), resolution),
+            Change::New | Change::Unchanged => String::new(),
+        };
+        println!(
+            "{:<9}  {}{}",
+            item.change.to_string(),
+            item.incoming.show.title,
+            details
+        );
+    }
+
+    if !dry_run {
+        let summary = import::apply(repository, &items)?;
+        println!(
+            "Added {}, updated {}, skipped {}",
+            summary.added, summary.updated, summary.skipped
+        );
+    }
+    Ok(())
+}
+
+fn refresh(
+    repository: &Repository,
+    config: &Config,
+    title: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let shows = match title {
+        Some(title) => vec![find_show(repository, title)?],
+        None => refresh::watching(repository)?,
+    };
+    let items = refresh::preview(repository, &shows, |link| sites::fetch(link, config))?;
+
+    for item in &items {
+        match &item.fetched {
+            Err(e) => println!("{:<9}  {}  {}", "failed", item.local.title, e),
+            Ok(_) => {
+                let label = match item.result() {
+                    Some(_) => "changed",
+                    None => "unchanged",
+                };
+                println!("{:<9}  {}", label, item.local.title);
+                for change in &item.changes {
+                    println!("{:<9}    {}", "", change);
+                }
+            }
+        }
+    }
+
+    if !dry_run {
+        let updated = refresh::apply(repository, &items, http::get_bytes)?;
+        println!("Updated {}", updated);
+    }
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "watchlist";
+const CONFIG_FILE: &str = "config.toml";
+const DATABASE_FILE: &str = "watchlist.db";
+const DATABASE_ARG: &str = "--database";
+const DATABASE_ENV: &str = "WATCHLIST_DB";
+
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub database: Option<PathBuf>,
+    /// The v3 API key of themoviedb.org, needed to import from it.
+    pub tmdb_api_key: Option<String>,
+    /// The language shows are imported in, like `uk-UA`. Sites pick one when it is not set.
+    pub language: Option<String>,
+    /// Languages of single sites, by their names in lower case, such as `imdb = "en-US"` or
+    /// `"web page" = "en-US"` for other pages.
+    pub languages: HashMap<String, String>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/watchlist/config.toml` or the platform equivalent.
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join(APP_NAME).join(CONFIG_FILE))
+    }
+
+    pub fn load() -> Result<Config> {
+        match Config::path() {
+            Some(path) if path.exists() => Config::from_file(&path),
+            _ => Ok(Config::default()),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        Config::parse(&content)
+            .with_context(|| format!("Failed to parse config {}", path.display()))
+    }
+
+    pub fn parse(content: &str) -> Result<Config> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// The language to import from the site in, the one set for the site or else `language`.
+    pub fn language(&self, site: &str) -> Option<&str> {
+        self.languages
+            .get(&site.to_lowercase())
+            .or(self.language.as_ref())
+            .map(String::as_str)
+            .filter(|language| !language.is_empty())
+    }
+}
+
+/// Reads `--database <path>` or `--database=<path>` from the command line arguments.
+pub fn database_arg(args: impl IntoIterator<Item = String>) -> Option<PathBuf> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == DATABASE_ARG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(path) = arg
+            .strip_prefix(DATABASE_ARG)
+            .and_then(|a| a.strip_prefix('='))
+        {
+            return Some(PathBuf::from(path));
+        }
+    }
+    None
+}
+
+/// `$XDG_DATA_HOME/watchlist/watchlist.db` or the platform equivalent.
+pub fn default_database_path() -> Result<PathBuf> {
+    let data_dir = dirs::data_dir().context("Failed to find the user data directory")?;
+    Ok(data_dir.join(APP_NAME).join(DATABASE_FILE))
+}
+
+/// Where older versions kept the database: next to the executable.
+pub fn legacy_database_path() -> Option<PathBuf> {
+    let mut path = std::env::current_exe().ok()?;
+    path.pop();
+    path.push(DATABASE_FILE);
+    Some(path)
+}
+
+/// Picks the database path by priority: command line, environment, config file, default.
+/// Returns `None` when nothing overrides the default location.
+pub fn database_override(
+    arg: Option<PathBuf>,
+    env: Option<PathBuf>,
+    config: &Config,
+) -> Option<PathBuf> {
+    arg.or(env)
+        .or_else(|| config.database.clone())
+        .filter(|p| !p.as_os_str().is_empty())
+}
+
+/// Moves a database left by an older version to the new location, unless one is already there.
+pub fn move_legacy_database(legacy: &Path, target: &Path) -> Result<bool> {
+    if target.exists() || !legacy.is_file() {
+        return Ok(false);
+    }
+
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+
+    // Renaming fails across file systems, so fall back to copying.
+    if std::fs::rename(legacy, target).is_err() {
+        std::fs::copy(legacy, target).with_context(|| {
+            format!(
+                "Failed to copy {} to {}",
+                legacy.display(),
+                target.display()
+            )
+        })?;
+        // The copy is in use from now on, so an old file that cannot be removed, such as one
+        // in a read-only install directory, is only left behind.
+        if let Err(e) = std::fs::remove_file(legacy) {
+            eprintln!("Warning: failed to remove {}: {}", legacy.display(), e);
+        }
+    }
+    Ok(true)
+}
+
+/// Resolves the database path, given the value of the `--database` argument if there was one,
+/// and makes sure its directory exists.
+pub fn database_path(arg: Option<PathBuf>, config: &Config) -> Result<PathBuf> {
+    let path = match database_override(
+        arg,
+        std::env::var_os(DATABASE_ENV).map(PathBuf::from),
+        config,
+    ) {
+        Some(path) => path,
+        None => {
+            let path = default_database_path()?;
+            if let Some(legacy) = legacy_database_path() {
+                if move_legacy_database(&legacy, &path)? {
+                    eprintln!(
+                        "Moved database from {} to {}",
+                        legacy.display(),
+                        path.display()
+                    );
+                }
+            }
+            path
+        }
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    Ok(path)
+}
@@ -1,22 +1,32 @@
 use std::{
-    fs::File, io::Read, path::PathBuf, rc::Rc, sync::{Arc, Mutex}, thread
+    fs::File,
+    io::{Cursor, Read},
+    path::Path,
 };
 
-use crate::{datetime::*, AppWindow, Show, ShowType, Status};
-use anyhow::{Context, Result};
-use image::EncodableLayout;
-use slint::{ComponentHandle, Model, ModelRc, Rgba8Pixel, SharedPixelBuffer, VecModel};
-use sqlite::State;
-
-fn get_database_name() -> PathBuf {
-    let mut path = std::env::current_exe().unwrap();
-    path.pop();
-    path.push("watchlist.db");
-    path
-}
-
-pub fn create() -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
+use crate::model::{Schedule, ShowRecord, ShowType, Status};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::Local;
+use image::ImageFormat;
+use sqlite::{Connection, State};
+
+type Migration = fn(&Connection) -> Result<()>;
+
+/// Ordered schema upgrades. `PRAGMA user_version` stores how many of them were applied,
+/// so a step must never be edited or reordered once released, only appended.
+const MIGRATIONS: &[Migration] = &[
+    create_list_table,
+    create_watch_events_table,
+    normalize_favorite,
+    store_pictures_as_blobs,
+    track_edited_fields,
+];
+
+const THUMBNAIL_WIDTH: u32 = 200;
+const THUMBNAIL_HEIGHT: u32 = 300;
+
+fn create_list_table(connection: &Connection) -> Result<()> {
+    // Databases created before migrations existed already have this table at version 0.
     let query = "CREATE TABLE IF NOT EXISTS list (
                      id INTEGER PRIMARY KEY AUTOINCREMENT,
                      title TEXT NOT NULL UNIQUE,
@@ -44,211 +54,376 @@ pub fn create() -> Result<()> {
     connection
         .execute(query)
         .context("Failed to create table")?;
+    Ok(())
+}
 
+fn create_watch_events_table(connection: &Connection) -> Result<()> {
+    let query = "CREATE TABLE IF NOT EXISTS watch_events (
+                     id INTEGER PRIMARY KEY AUTOINCREMENT,
+                     show_id INTEGER NOT NULL REFERENCES list(id) ON DELETE CASCADE,
+                     season INTEGER NOT NULL,
+                     episode INTEGER NOT NULL,
+                     watched_at TEXT,
+                     rating INTEGER,
+                     note TEXT
+                 );
+                 CREATE INDEX IF NOT EXISTS watch_events_show_id ON watch_events(show_id);";
+    connection
+        .execute(query)
+        .context("Failed to create watch_events table")?;
+
+    // Seed the history with the progress that existing shows already have, so rolling back
+    // the first new event returns to it. The time it was watched is unknown.
+    let query = "INSERT INTO watch_events(show_id, season, episode, watched_at)
+                 SELECT id, IFNULL(season, 1), episode, NULL FROM list
+                 WHERE episode > 0
+                     AND NOT EXISTS (SELECT 1 FROM watch_events WHERE show_id = list.id);";
+    connection
+        .execute(query)
+        .context("Failed to fill watch history")?;
     Ok(())
 }
 
-fn rows_count() -> Result<u32> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let mut statement = connection.prepare("SELECT COUNT(*) FROM list;")?;
-    statement.next()?;
-    let count: i64 = statement.read::<i64, _>(0)?;
-    Ok(count as u32)
+fn normalize_favorite(connection: &Connection) -> Result<()> {
+    // Older versions stored the flag either as 0/1 or as the text "true"/"false".
+    connection
+        .execute("UPDATE list SET favorite = CASE WHEN favorite IN (1, 'true') THEN 1 ELSE 0 END;")
+        .context("Failed to normalize favorite flags")?;
+    Ok(())
 }
 
-fn execute_query(query: &str) -> Result<ModelRc<Show>> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let mut statement = connection.prepare(query)?;
-    let mut model = Vec::new();
-    let mut index = 0;
-
-    while let Ok(State::Row) = statement.next() {
-        let st = statement.read::<i64, _>("status")?;
-        let status = match st {
-            1 => Status::Watching,
-            2 => Status::Completed,
-            3 => Status::Dropped,
-            _ => Status::WatchLater,
-        };
-        let sht = statement.read::<i64, _>("show_type")?;
-        let show_type = match sht {
-            1 => ShowType::Film,
-            2 => ShowType::Cartoon,
-            3 => ShowType::Anime,
-            _ => ShowType::Serial,
-        };
+fn column_exists(connection: &Connection, table: &str, column: &str) -> Result<bool> {
+    let mut statement = connection.prepare(format!("PRAGMA table_info({});", table))?;
+    while let State::Row = statement.next()? {
+        if statement.read::<String, _>("name")? == column {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
 
-        let episode = statement.read::<i64, _>("episode")? as i32;
-        let episodes_count = statement.read::<i64, _>("episodes_count")? as i32;
-        let schedule_monday = statement.read::<i64, _>("schedule_monday")? as i32;
-        let schedule_tuesday = statement.read::<i64, _>("schedule_tuesday")? as i32;
-        let schedule_wednesday = statement.read::<i64, _>("schedule_wednesday")? as i32;
-        let schedule_thursday = statement.read::<i64, _>("schedule_thursday")? as i32;
-        let schedule_friday = statement.read::<i64, _>("schedule_friday")? as i32;
-        let schedule_saturday = statement.read::<i64, _>("schedule_saturday")? as i32;
-        let schedule_sunday = statement.read::<i64, _>("schedule_sunday")? as i32;
-        let release_time = statement.read::<String, _>("release_time")?;
-        let new_episodes_available = check_new_episodes_available(
-            &release_time,
-            episode as u32,
-            [
-                schedule_monday as u32,
-                schedule_tuesday as u32,
-                schedule_wednesday as u32,
-                schedule_thursday as u32,
-                schedule_friday as u32,
-                schedule_saturday as u32,
-                schedule_sunday as u32,
-            ],
-        )
-        .unwrap_or_default();
-
-        let show = Show {
-            id: statement.read::<i64, _>("id")? as i32,
-            index,
-            title: statement.read::<String, _>("title")?.into(),
-            alternative_title: statement.read::<String, _>("alternative_title")?.into(),
-            release_date: statement.read::<String, _>("release_date")?.into(),
-            about: statement.read::<String, _>("about")?.into(),
-            link_to_show: statement.read::<String, _>("link_to_show")?.into(),
-            score: statement.read::<i64, _>("score")? as i32,
-            favorite: statement.read::<String, _>("favorite")?.eq("true"),
-            status,
-            show_type,
-            season: statement.read::<i64, _>("season")? as i32,
-            episodes_count,
-            episode,
-            release_time: release_time.into(),
-            schedule_monday,
-            schedule_tuesday,
-            schedule_wednesday,
-            schedule_thursday,
-            schedule_friday,
-            schedule_saturday,
-            schedule_sunday,
-            new_episodes_available,
-            ..Default::default()
-        };
-        model.push(show);
-        index += 1;
+fn store_pictures_as_blobs(connection: &Connection) -> Result<()> {
+    if !column_exists(connection, "list", "thumbnail")? {
+        connection.execute("ALTER TABLE list ADD COLUMN thumbnail BLOB;")?;
     }
 
-    let shows: Rc<VecModel<Show>> = Rc::new(VecModel::from(model));
-    Ok(ModelRc::from(shows))
-}
+    // Pictures used to be bound as TEXT holding the raw file bytes.
+    connection
+        .execute(
+            "UPDATE list SET image = CAST(image AS BLOB) WHERE typeof(image) = 'text';
+             UPDATE list SET image = NULL WHERE length(image) = 0;",
+        )
+        .context("Failed to convert pictures to blobs")?;
+
+    let mut pictures = Vec::new();
+    let mut statement = connection
+        .prepare("SELECT id, image FROM list WHERE image IS NOT NULL AND thumbnail IS NULL;")?;
+    while let State::Row = statement.next()? {
+        pictures.push((
+            statement.read::<i64, _>("id")?,
+            statement.read::<Vec<u8>, _>("image")?,
+        ));
+    }
 
-fn load_images(ui: slint::Weak<AppWindow>) -> Result<()> {
-    let query = "SELECT image FROM list
-        ORDER BY
-            CASE status
-                WHEN 1 THEN 0
-                WHEN 0 THEN 1
-                WHEN 2 THEN 2
-                WHEN 3 THEN 3
-                ELSE 4
-            END,
-            id DESC;
-    ";
-    let rows_number = rows_count()?;
-
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let mut statement = connection.prepare(query)?;
-    let model = Arc::new(Mutex::new(Vec::new()));
-    let mut index = 0;
-
-    while let Ok(State::Row) = statement.next() {
-        let picture_blob = statement.read::<Vec<u8>, _>("image");
-        if let Ok(content) = picture_blob {
-            if content.is_empty() {
-                model.lock().unwrap().push(None);
-            } else {
-                let picture_image = image::load_from_memory(content.as_bytes())
-                    .expect("Failed to load picture from memory")
-                    .into_rgba8();
-                let buffer = SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
-                    picture_image.as_raw(),
-                    picture_image.width(),
-                    picture_image.height(),
-                );
-                model.lock().unwrap().push(Some(buffer));
+    for (id, content) in pictures {
+        match make_thumbnail(&content) {
+            Ok(thumbnail) => {
+                let mut statement =
+                    connection.prepare("UPDATE list SET thumbnail = ? WHERE id = ?;")?;
+                statement.bind((1, thumbnail.as_slice()))?;
+                statement.bind((2, id))?;
+                statement.next()?;
             }
-        } else {
-            model.lock().unwrap().push(None);
+            Err(e) => eprintln!("Failed to create thumbnail for show {}: {}", id, e),
         }
+    }
+    Ok(())
+}
 
-        index += 1;
-        let loading_progress = index as f32 / rows_number as f32 * 100.0;
-        let ui_clone = ui.clone();
-        _ = slint::invoke_from_event_loop(move || {
-            if let Some(app) = ui_clone.upgrade() {
-                app.set_loading_progress(loading_progress as i32);
-            }
-        });
+fn track_edited_fields(connection: &Connection) -> Result<()> {
+    let query = "CREATE TABLE IF NOT EXISTS edited_fields (
+                     show_id INTEGER NOT NULL REFERENCES list(id) ON DELETE CASCADE,
+                     field TEXT NOT NULL,
+                     PRIMARY KEY (show_id, field)
+                 );";
+    connection
+        .execute(query)
+        .context("Failed to create edited_fields table")?;
+
+    // The link a picture was downloaded from, to tell when the site has a new one.
+    if !column_exists(connection, "list", "picture_url")? {
+        connection.execute("ALTER TABLE list ADD COLUMN picture_url TEXT;")?;
     }
+    Ok(())
+}
 
-    let ui_clone = ui.clone();
-    _ = slint::invoke_from_event_loop(move || {
-        let model = model.lock().unwrap();
-        if let Some(app) = ui_clone.upgrade() {
-            let shows = app.get_shows();
-            for i in 0..shows.row_count() {
-                if let Some(buffer) = model[i].clone() {
-                    let picture = slint::Image::from_rgba8(buffer);
-                    let mut s = shows.row_data(i).unwrap();
-                    s.picture = picture;
-                    shows.set_row_data(i, s);
-                }
+pub fn schema_version(connection: &Connection) -> Result<usize> {
+    let mut statement = connection.prepare("PRAGMA user_version;")?;
+    statement.next()?;
+    let version = statement.read::<i64, _>(0)?;
+    Ok(version as usize)
+}
+
+pub fn latest_schema_version() -> usize {
+    MIGRATIONS.len()
+}
+
+pub fn migrate(connection: &Connection) -> Result<()> {
+    let version = schema_version(connection)?;
+    if version > latest_schema_version() {
+        bail!(
+            "Database schema version {} is newer than supported version {}, please update the application",
+            version,
+            latest_schema_version()
+        );
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let next_version = index + 1;
+        connection.execute("BEGIN;")?;
+        let result = migration(connection).and_then(|_| {
+            connection.execute(format!("PRAGMA user_version = {};", next_version))?;
+            Ok(())
+        });
+        match result {
+            Ok(_) => connection.execute("COMMIT;")?,
+            Err(e) => {
+                _ = connection.execute("ROLLBACK;");
+                return Err(e.context(format!(
+                    "Failed to migrate database to version {}",
+                    next_version
+                )));
             }
-            app.set_shows(shows);
         }
-    });
+    }
 
     Ok(())
 }
 
-pub fn load_watchlist(ui: &AppWindow, is_watchlist_loaded: Arc<Mutex<bool>>) -> Result<()> {
-    let query = "SELECT * FROM list
-        ORDER BY
-            CASE status
-                WHEN 1 THEN 0
-                WHEN 0 THEN 1
-                WHEN 2 THEN 2
-                WHEN 3 THEN 3
-                ELSE 4
-            END,
-            id DESC;
-    ";
-    let shows = execute_query(query)?;
-    ui.invoke_set_shows(shows);
-
-    let ui_weak = ui.as_weak();
-    thread::spawn(move || {
-        _ = load_images(ui_weak).map_err(|e| eprintln!("Failed to load images: {e}"));
-        let mut is_watchlist_loaded = is_watchlist_loaded.lock().unwrap();
-        *is_watchlist_loaded = true;
-    });
-    Ok(())
+/// Downscales a picture to fit the list view and encodes it as PNG.
+pub fn make_thumbnail(content: &[u8]) -> Result<Vec<u8>> {
+    let picture = image::load_from_memory(content).context("Failed to decode picture")?;
+    let thumbnail = picture.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT);
+    let mut buffer = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut buffer, ImageFormat::Png)
+        .context("Failed to encode thumbnail")?;
+    Ok(buffer.into_inner())
 }
 
-pub fn add_show(s: &Show) -> Result<()> {
-    let status = match s.status {
-        Status::WatchLater => 0,
-        Status::Watching => 1,
-        Status::Completed => 2,
-        Status::Dropped => 3,
-    };
-
-    let show_type = match s.show_type {
-        ShowType::Serial => 0,
-        ShowType::Film => 1,
-        ShowType::Cartoon => 2,
-        ShowType::Anime => 3,
-    };
-
-    let connection = sqlite::open(get_database_name()).expect("Failed to connect to database");
-
-    if s.id != 0 {
-        let query = "UPDATE list SET
+/// How `watched_at` is stored, in local time.
+pub const WATCHED_AT_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WatchEvent {
+    pub id: i64,
+    pub show_id: i64,
+    pub season: i32,
+    pub episode: i32,
+    pub watched_at: Option<String>,
+    pub rating: Option<i32>,
+    pub note: Option<String>,
+}
+
+impl WatchEvent {
+    /// Whether both events record the same watch, wherever they come from.
+    pub fn is_same_watch(&self, other: &WatchEvent) -> bool {
+        (self.season, self.episode, &self.watched_at)
+            == (other.season, other.episode, &other.watched_at)
+    }
+}
+
+fn read_watch_event(statement: &sqlite::Statement) -> Result<WatchEvent> {
+    Ok(WatchEvent {
+        id: statement.read::<i64, _>("id")?,
+        show_id: statement.read::<i64, _>("show_id")?,
+        season: statement.read::<i64, _>("season")? as i32,
+        episode: statement.read::<i64, _>("episode")? as i32,
+        watched_at: statement.read::<Option<String>, _>("watched_at")?,
+        rating: statement
+            .read::<Option<i64>, _>("rating")?
+            .map(|rating| rating as i32),
+        note: statement.read::<Option<String>, _>("note")?,
+    })
+}
+
+/// Rows written by older versions may hold NULL in any column.
+fn read_text(statement: &sqlite::Statement, column: &str) -> Result<String> {
+    Ok(statement
+        .read::<Option<String>, _>(column)?
+        .unwrap_or_default())
+}
+
+fn read_integer(statement: &sqlite::Statement, column: &str) -> Result<i64> {
+    Ok(statement
+        .read::<Option<i64>, _>(column)?
+        .unwrap_or_default())
+}
+
+/// Titles are unique, so a second show with a title is refused rather than replacing the first.
+fn is_duplicate_title(e: &sqlite::Error) -> bool {
+    e.message
+        .as_deref()
+        .is_some_and(|m| m.contains("UNIQUE constraint failed: list.title"))
+}
+
+fn read_show(statement: &sqlite::Statement) -> Result<ShowRecord> {
+    Ok(ShowRecord {
+        id: statement.read::<i64, _>("id")?,
+        title: read_text(statement, "title")?,
+        alternative_title: read_text(statement, "alternative_title")?,
+        release_date: read_text(statement, "release_date")?,
+        about: read_text(statement, "about")?,
+        link_to_show: read_text(statement, "link_to_show")?,
+        link_to_picture: String::new(),
+        score: read_integer(statement, "score")? as i32,
+        favorite: read_integer(statement, "favorite")? != 0,
+        status: Status::from_db(read_integer(statement, "status")?),
+        show_type: ShowType::from_db(read_integer(statement, "show_type")?),
+        season: read_integer(statement, "season")? as i32,
+        episodes_count: read_integer(statement, "episodes_count")? as i32,
+        episode: read_integer(statement, "episode")? as i32,
+        schedule: Schedule {
+            release_time: read_text(statement, "release_time")?,
+            episodes_per_weekday: [
+                read_integer(statement, "schedule_monday")? as u32,
+                read_integer(statement, "schedule_tuesday")? as u32,
+                read_integer(statement, "schedule_wednesday")? as u32,
+                read_integer(statement, "schedule_thursday")? as u32,
+                read_integer(statement, "schedule_friday")? as u32,
+                read_integer(statement, "schedule_saturday")? as u32,
+                read_integer(statement, "schedule_sunday")? as u32,
+            ],
+        },
+    })
+}
+
+/// Columns of `list` needed to display a show, without the pictures.
+const SHOW_COLUMNS: &str = "id, title, alternative_title, release_date, about, link_to_show,
+    score, favorite, status, show_type, season, episodes_count, episode, release_time,
+    schedule_monday, schedule_tuesday, schedule_wednesday, schedule_thursday,
+    schedule_friday, schedule_saturday, schedule_sunday";
+
+/// Watching first, then planned, completed and dropped; newest first within each status.
+const WATCHLIST_ORDER: &str = "ORDER BY
+    CASE status
+        WHEN 1 THEN 0
+        WHEN 0 THEN 1
+        WHEN 2 THEN 2
+        WHEN 3 THEN 3
+        ELSE 4
+    END,
+    id DESC";
+
+/// The watchlist database. Owns a single connection that is reused by every operation.
+pub struct Repository {
+    connection: Connection,
+}
+
+impl Repository {
+    pub fn open(path: &Path) -> Result<Repository> {
+        let connection = sqlite::open(path)
+            .with_context(|| format!("Failed to open database {}", path.display()))?;
+        Repository::from_connection(connection)
+    }
+
+    /// Configures the connection and brings the schema up to date.
+    pub fn from_connection(connection: Connection) -> Result<Repository> {
+        connection
+            .execute(
+                "PRAGMA journal_mode = WAL;
+                 PRAGMA busy_timeout = 5000;
+                 PRAGMA foreign_keys = ON;",
+            )
+            .context("Failed to configure database")?;
+        migrate(&connection)?;
+        Ok(Repository { connection })
+    }
+
+    #[cfg(test)]
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Runs `f` atomically. Transactions may be nested.
+    pub fn transaction<T>(&self, f: impl FnOnce() -> Result<T>) -> Result<T> {
+        self.connection.execute("SAVEPOINT repository;")?;
+        match f() {
+            Ok(value) => {
+                self.connection.execute("RELEASE repository;")?;
+                Ok(value)
+            }
+            Err(e) => {
+                _ = self
+                    .connection
+                    .execute("ROLLBACK TO repository; RELEASE repository;");
+                Err(e)
+            }
+        }
+    }
+
+    pub fn load_shows(&self) -> Result<Vec<ShowRecord>> {
+        let query = format!("SELECT {} FROM list {};", SHOW_COLUMNS, WATCHLIST_ORDER);
+        let mut statement = self.connection.prepare(query)?;
+        let mut shows = Vec::new();
+        while let State::Row = statement.next()? {
+            shows.push(read_show(&statement)?);
+        }
+        Ok(shows)
+    }
+
+    pub fn load_show(&self, id: i64) -> Result<ShowRecord> {
+        let query = format!("SELECT {} FROM list WHERE id = ?;", SHOW_COLUMNS);
+        let mut statement = self.connection.prepare(query)?;
+        statement.bind((1, id))?;
+        match statement.next()? {
+            State::Row => read_show(&statement),
+            State::Done => bail!("There is no show with id {}", id),
+        }
+    }
+
+    /// Thumbnails of all shows, in the same order as [`Repository::load_shows`].
+    pub fn load_thumbnails(&self) -> Result<Vec<Option<Vec<u8>>>> {
+        let query = format!("SELECT thumbnail FROM list {};", WATCHLIST_ORDER);
+        let mut statement = self.connection.prepare(query)?;
+        let mut thumbnails = Vec::new();
+        while let State::Row = statement.next()? {
+            let thumbnail = statement.read::<Option<Vec<u8>>, _>("thumbnail")?;
+            thumbnails.push(thumbnail.filter(|content| !content.is_empty()));
+        }
+        Ok(thumbnails)
+    }
+
+    /// Reads the full-size picture of a show, which the list only keeps as a thumbnail.
+    pub fn read_picture(&self, id: i64) -> Result<Option<Vec<u8>>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT image FROM list WHERE id = ?;")?;
+        statement.bind((1, id))?;
+        match statement.next()? {
+            State::Row => Ok(statement
+                .read::<Option<Vec<u8>>, _>("image")?
+                .filter(|content| !content.is_empty())),
+            State::Done => Ok(None),
+        }
+    }
+
+    /// Inserts a new show when `id` is 0, otherwise updates it. Returns the id of the row.
+    /// Fails without touching the watchlist when another show has the same title.
+    pub fn save_show(&self, s: &ShowRecord) -> Result<i64> {
+        self.transaction(|| self.save_show_fields(s)).map_err(|e| {
+            match e.downcast_ref::<sqlite::Error>() {
+                Some(e) if is_duplicate_title(e) => {
+                    anyhow!("\"{}\" is already in the watchlist", s.title)
+                }
+                _ => e.context(format!("Failed to save show \"{}\"", s.title)),
+            }
+        })
+    }
+
+    fn save_show_fields(&self, s: &ShowRecord) -> Result<i64> {
+        let query = if s.id != 0 {
+            "UPDATE list SET
                 title = ?,
                 alternative_title = ?,
                 release_date = ?,
@@ -269,8 +444,19 @@ pub fn add_show(s: &Show) -> Result<()> {
                 schedule_saturday = ?,
                 schedule_sunday = ?,
                 show_type = ?
-            WHERE id = ?;";
-        let mut statement = connection.prepare(query)?;
+            WHERE id = ?;"
+        } else {
+            "INSERT INTO list(title, alternative_title, release_date, about, link_to_show,
+                    score, favorite, status, season, episodes_count, episode, release_time,
+                    schedule_monday, schedule_tuesday, schedule_wednesday,
+                    schedule_thursday, schedule_friday, schedule_saturday,
+                    schedule_sunday, show_type) VALUES
+                    (?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+                     ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);"
+        };
+
+        let mut statement = self.connection.prepare(query)?;
+        let schedule = &s.schedule.episodes_per_weekday;
         statement.bind((1, s.title.as_str()))?;
         statement.bind((2, s.alternative_title.as_str()))?;
         statement.bind((3, s.release_date.as_str()))?;
@@ -278,146 +464,301 @@ pub fn add_show(s: &Show) -> Result<()> {
         statement.bind((5, s.link_to_show.as_str()))?;
         statement.bind((6, s.score as i64))?;
         statement.bind((7, s.favorite as i64))?;
-        statement.bind((8, status as i64))?;
+        statement.bind((8, s.status.to_db()))?;
         statement.bind((9, s.season as i64))?;
         statement.bind((10, s.episodes_count as i64))?;
         statement.bind((11, s.episode as i64))?;
-        statement.bind((12, s.release_time.as_str()))?;
-        statement.bind((13, s.schedule_monday as i64))?;
-        statement.bind((14, s.schedule_tuesday as i64))?;
-        statement.bind((15, s.schedule_wednesday as i64))?;
-        statement.bind((16, s.schedule_thursday as i64))?;
-        statement.bind((17, s.schedule_friday as i64))?;
-        statement.bind((18, s.schedule_saturday as i64))?;
-        statement.bind((19, s.schedule_sunday as i64))?;
-        statement.bind((20, show_type))?;
-        statement.bind((21, s.id as i64))?;
+        statement.bind((12, s.schedule.release_time.as_str()))?;
+        statement.bind((13, schedule[0] as i64))?;
+        statement.bind((14, schedule[1] as i64))?;
+        statement.bind((15, schedule[2] as i64))?;
+        statement.bind((16, schedule[3] as i64))?;
+        statement.bind((17, schedule[4] as i64))?;
+        statement.bind((18, schedule[5] as i64))?;
+        statement.bind((19, schedule[6] as i64))?;
+        statement.bind((20, s.show_type.to_db()))?;
+        if s.id != 0 {
+            statement.bind((21, s.id))?;
+        }
         statement.next()?;
-    } else {
-        let query = "REPLACE INTO list(title, alternative_title, release_date, about, link_to_show,
-                            score, favorite, status, season, episodes_count, episode, release_time,
-                            schedule_monday, schedule_tuesday, schedule_wednesday,
-                            schedule_thursday, schedule_friday, schedule_saturday,
-                            schedule_sunday, show_type) VALUES
-                            (?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
-                             ?, ?, ?, ?, ?, ?, ?, ?, ?, ?);";
-        let mut statement = connection.prepare(query)?;
-        statement.bind((1, s.title.as_str()))?;
-        statement.bind((2, s.alternative_title.as_str()))?;
-        statement.bind((3, s.release_date.as_str()))?;
-        statement.bind((4, s.about.as_str()))?;
-        statement.bind((5, s.link_to_show.as_str()))?;
-        statement.bind((6, s.score as i64))?;
-        statement.bind((7, s.favorite as i64))?;
-        statement.bind((8, status as i64))?;
-        statement.bind((9, s.season as i64))?;
-        statement.bind((10, s.episodes_count as i64))?;
-        statement.bind((11, s.episode as i64))?;
-        statement.bind((12, s.release_time.as_str()))?;
-        statement.bind((13, s.schedule_monday as i64))?;
-        statement.bind((14, s.schedule_tuesday as i64))?;
-        statement.bind((15, s.schedule_wednesday as i64))?;
-        statement.bind((16, s.schedule_thursday as i64))?;
-        statement.bind((17, s.schedule_friday as i64))?;
-        statement.bind((18, s.schedule_saturday as i64))?;
-        statement.bind((19, s.schedule_sunday as i64))?;
-        statement.bind((20, show_type))?;
+
+        let show_id = if s.id != 0 {
+            s.id
+        } else {
+            let mut statement = self.connection.prepare("SELECT last_insert_rowid();")?;
+            statement.next()?;
+            statement.read::<i64, _>(0)?
+        };
+        self.record_watch_event(show_id, s.season, s.episode, None, None)?;
+
+        if !s.link_to_picture.is_empty() {
+            let mut content = Vec::new();
+            let mut file = File::open(&s.link_to_picture)
+                .with_context(|| format!("Failed to open picture {}", s.link_to_picture))?;
+            file.read_to_end(&mut content)
+                .with_context(|| format!("Failed to read picture {}", s.link_to_picture))?;
+            self.update_picture(show_id, &content)?;
+            // A picture from a file is never replaced by refreshing the show.
+            let mut statement = self
+                .connection
+                .prepare("UPDATE list SET picture_url = NULL WHERE id = ?;")?;
+            statement.bind((1, show_id))?;
+            statement.next()?;
+        }
+
+        Ok(show_id)
+    }
+
+    pub fn update_picture(&self, id: i64, content: &[u8]) -> Result<()> {
+        let thumbnail = make_thumbnail(content)?;
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET image = ?, thumbnail = ? WHERE id = ?;")?;
+        statement.bind((1, content))?;
+        statement.bind((2, thumbnail.as_slice()))?;
+        statement.bind((3, id))?;
         statement.next()?;
+        Ok(())
     }
 
-    if !s.link_to_picture.is_empty() {
-        let query = format!("UPDATE list SET image = ? WHERE title = \"{}\";", s.title);
+    /// Where the picture of a show was downloaded from. `None` when the show has a picture of
+    /// unknown origin, an empty link when it has none.
+    pub fn picture_url(&self, id: i64) -> Result<Option<String>> {
+        let mut statement = self.connection.prepare(
+            "SELECT picture_url, image IS NOT NULL AS has_picture FROM list WHERE id = ?;",
+        )?;
+        statement.bind((1, id))?;
+        if let State::Done = statement.next()? {
+            return Ok(Some(String::new()));
+        }
+        match statement.read::<Option<String>, _>("picture_url")? {
+            Some(url) => Ok(Some(url)),
+            None if read_integer(&statement, "has_picture")? != 0 => Ok(None),
+            None => Ok(Some(String::new())),
+        }
+    }
 
-        let mut content = Vec::new();
-        let st = if !s.link_to_picture.is_empty() {
-            let mut file = File::open(s.link_to_picture.to_string()).expect("Failed to open file");
-            file.read_to_end(&mut content)
-                .expect("Failed to read_to_end");
-            unsafe { std::str::from_utf8_unchecked(&content) }
-        } else {
-            ""
-        };
+    pub fn update_picture_url(&self, id: i64, url: &str) -> Result<()> {
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET picture_url = ? WHERE id = ?;")?;
+        statement.bind((1, url))?;
+        statement.bind((2, id))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    /// Names of the fields of a show that were typed by hand rather than imported.
+    pub fn edited_fields(&self, show_id: i64) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT field FROM edited_fields WHERE show_id = ? ORDER BY field;")?;
+        statement.bind((1, show_id))?;
+
+        let mut fields = Vec::new();
+        while let State::Row = statement.next()? {
+            fields.push(statement.read::<String, _>("field")?);
+        }
+        Ok(fields)
+    }
+
+    pub fn mark_edited(&self, show_id: i64, fields: &[&str]) -> Result<()> {
+        for field in fields {
+            let mut statement = self
+                .connection
+                .prepare("INSERT OR IGNORE INTO edited_fields(show_id, field) VALUES (?, ?);")?;
+            statement.bind((1, show_id))?;
+            statement.bind((2, *field))?;
+            statement.next()?;
+        }
+        Ok(())
+    }
 
-        let mut statement = connection.prepare(query)?;
-        statement.bind((1, st))?;
+    /// Deletes a show together with its watch history.
+    pub fn delete_show(&self, id: i64) -> Result<()> {
+        let mut statement = self.connection.prepare("DELETE FROM list WHERE id = ?;")?;
+        statement.bind((1, id))?;
         statement.next()?;
+        Ok(())
     }
 
-    Ok(())
-}
+    pub fn update_score(&self, id: i64, score: i32) -> Result<()> {
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET score = ? WHERE id = ?;")?;
+        statement.bind((1, score as i64))?;
+        statement.bind((2, id))?;
+        statement.next()?;
+        Ok(())
+    }
 
-pub fn remove_show(show: &Show) -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!("DELETE FROM list WHERE title = \"{}\";", show.title);
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to delete show with title \"{}\"", show.title))?;
-    Ok(())
-}
+    pub fn update_status(&self, id: i64, status: Status) -> Result<()> {
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET status = ? WHERE id = ?;")?;
+        statement.bind((1, status.to_db()))?;
+        statement.bind((2, id))?;
+        statement.next()?;
+        Ok(())
+    }
 
-pub fn score_changed(show: &Show) -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!(
-        "UPDATE list SET score = \"{}\" WHERE id = \"{}\";",
-        show.score, show.id,
-    );
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to change score to {}", show.score))?;
-    Ok(())
-}
+    pub fn update_favorite(&self, id: i64, favorite: bool) -> Result<()> {
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET favorite = ? WHERE id = ?;")?;
+        statement.bind((1, favorite as i64))?;
+        statement.bind((2, id))?;
+        statement.next()?;
+        Ok(())
+    }
 
-pub fn status_changed(show: &Show) -> Result<()> {
-    let status = match show.status {
-        Status::WatchLater => 0,
-        Status::Watching => 1,
-        Status::Completed => 2,
-        Status::Dropped => 3,
-    };
-
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!(
-        "UPDATE list SET status = \"{}\" WHERE id = \"{}\";",
-        status, show.id,
-    );
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to change status to {}", show.score))?;
-    Ok(())
-}
+    /// Stores the show's season and episode and appends them to its watch history.
+    pub fn update_progress(&self, id: i64, season: i32, episode: i32) -> Result<()> {
+        self.transaction(|| {
+            let mut statement = self
+                .connection
+                .prepare("UPDATE list SET season = ?, episode = ? WHERE id = ?;")?;
+            statement.bind((1, season as i64))?;
+            statement.bind((2, episode as i64))?;
+            statement.bind((3, id))?;
+            statement.next()?;
+            self.record_watch_event(id, season, episode, None, None)
+        })
+    }
 
-pub fn favorite_changed(show: &Show) -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!(
-        "UPDATE list SET favorite = \"{}\" WHERE id = \"{}\";",
-        show.favorite, show.id,
-    );
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to change favorite to {}", show.score))?;
-    Ok(())
-}
+    /// Returns the watch history of a show, oldest event first.
+    pub fn watch_history(&self, show_id: i64) -> Result<Vec<WatchEvent>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT * FROM watch_events WHERE show_id = ? ORDER BY id;")?;
+        statement.bind((1, show_id))?;
 
-pub fn season_changed(show: &Show) -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!(
-        "UPDATE list SET season = \"{}\" WHERE id = \"{}\";",
-        show.season, show.id,
-    );
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to change season to {}", show.score))?;
-    Ok(())
-}
+        let mut history = Vec::new();
+        while let State::Row = statement.next()? {
+            history.push(read_watch_event(&statement)?);
+        }
+        Ok(history)
+    }
 
-pub fn episode_changed(show: &Show) -> Result<()> {
-    let connection = sqlite::open(get_database_name()).context("Failed to open database")?;
-    let query = format!(
-        "UPDATE list SET episode = \"{}\" WHERE id = \"{}\";",
-        show.episode, show.id,
-    );
-    connection
-        .execute(query)
-        .with_context(|| format!("Failed to change episode to {}", show.score))?;
-    Ok(())
+    pub fn last_watch_event(&self, show_id: i64) -> Result<Option<WatchEvent>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT * FROM watch_events WHERE show_id = ? ORDER BY id DESC LIMIT 1;")?;
+        statement.bind((1, show_id))?;
+
+        match statement.next()? {
+            State::Row => Ok(Some(read_watch_event(&statement)?)),
+            State::Done => Ok(None),
+        }
+    }
+
+    /// Appends an event unless the show is already at this season and episode.
+    pub fn record_watch_event(
+        &self,
+        show_id: i64,
+        season: i32,
+        episode: i32,
+        rating: Option<i32>,
+        note: Option<&str>,
+    ) -> Result<()> {
+        if let Some(last) = self.last_watch_event(show_id)? {
+            if last.season == season && last.episode == episode {
+                return Ok(());
+            }
+        } else if episode == 0 {
+            return Ok(());
+        }
+
+        let watched_at = Local::now().format(WATCHED_AT_FORMAT).to_string();
+        let mut statement = self.connection.prepare(
+            "INSERT INTO watch_events(show_id, season, episode, watched_at, rating, note)
+             VALUES (?, ?, ?, ?, ?, ?);",
+        )?;
+        statement.bind((1, show_id))?;
+        statement.bind((2, season as i64))?;
+        statement.bind((3, episode as i64))?;
+        statement.bind((4, watched_at.as_str()))?;
+        statement.bind((5, rating.map(|rating| rating as i64)))?;
+        statement.bind((6, note))?;
+        statement.next()?;
+        Ok(())
+    }
+
+    /// Merges events from elsewhere, such as another tracker, into the history of a show and
+    /// orders it by time. An event repeating the episode of the one before it is dropped, like
+    /// `record_watch_event` does. The show's progress is left as it is.
+    pub fn import_watch_events(&self, show_id: i64, events: &[WatchEvent]) -> Result<()> {
+        self.transaction(|| {
+            let mut history = self.watch_history(show_id)?;
+            for event in events {
+                if !history.iter().any(|e| e.is_same_watch(event)) {
+                    history.push(event.clone());
+                }
+            }
+            history.sort_by(|a, b| a.watched_at.cmp(&b.watched_at));
+            history.dedup_by(|e, previous| {
+                (e.season, e.episode) == (previous.season, previous.episode)
+            });
+
+            let mut statement = self
+                .connection
+                .prepare("DELETE FROM watch_events WHERE show_id = ?;")?;
+            statement.bind((1, show_id))?;
+            statement.next()?;
+
+            for event in history {
+                let mut statement = self.connection.prepare(
+                    "INSERT INTO watch_events(show_id, season, episode, watched_at, rating, note)
+                     VALUES (?, ?, ?, ?, ?, ?);",
+                )?;
+                statement.bind((1, show_id))?;
+                statement.bind((2, event.season as i64))?;
+                statement.bind((3, event.episode as i64))?;
+                statement.bind((4, event.watched_at.as_deref()))?;
+                statement.bind((5, event.rating.map(|rating| rating as i64)))?;
+                statement.bind((6, event.note.as_deref()))?;
+                statement.next()?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Removes the most recent event and returns it.
+    pub fn rollback_last_watch_event(&self, show_id: i64) -> Result<Option<WatchEvent>> {
+        let last = self.last_watch_event(show_id)?;
+        if let Some(event) = &last {
+            let mut statement = self
+                .connection
+                .prepare("DELETE FROM watch_events WHERE id = ?;")?;
+            statement.bind((1, event.id))?;
+            statement.next()?;
+        }
+        Ok(last)
+    }
+
+    /// Sets the show's `season` and `episode` to its latest watch event, or to the very
+    /// beginning when there is no history, and returns them.
+    pub fn recompute_progress(&self, show_id: i64) -> Result<(i32, i32)> {
+        let (season, episode) = match self.last_watch_event(show_id)? {
+            Some(event) => (event.season, event.episode),
+            None => (1, 0),
+        };
+
+        let mut statement = self
+            .connection
+            .prepare("UPDATE list SET season = ?, episode = ? WHERE id = ?;")?;
+        statement.bind((1, season as i64))?;
+        statement.bind((2, episode as i64))?;
+        statement.bind((3, show_id))?;
+        statement.next()?;
+        Ok((season, episode))
+    }
+
+    /// Rolls back the last watch event and returns the restored season and episode.
+    pub fn undo_progress(&self, show_id: i64) -> Result<(i32, i32)> {
+        self.transaction(|| {
+            self.rollback_last_watch_event(show_id)?;
+            self.recompute_progress(show_id)
+        })
+    }
 }
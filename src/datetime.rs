@@ -25,3 +25,44 @@ pub fn check_new_episodes_available(
 
     Ok(total_episodes > current_episode)
 }
+
+/// Episodes released together on one day of a schedule, numbered from the first release.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub time: NaiveDateTime,
+    pub first_episode: u32,
+    pub last_episode: u32,
+}
+
+/// Every release of a schedule from its first one, ending with episode `episodes_count`, or
+/// never when the number of episodes is unknown (0).
+pub fn releases(
+    time: &str,
+    schedule: [u32; 7],
+    episodes_count: u32,
+) -> Result<impl Iterator<Item = Release>> {
+    let start_time = NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M")?;
+    let has_releases = schedule.iter().any(|&episodes| episodes > 0);
+
+    Ok((0..)
+        .take_while(move |_| has_releases)
+        .map(move |day| start_time + chrono::Duration::days(day))
+        .scan(0, move |released, time| {
+            let first_episode = *released + 1;
+            *released += schedule[time.weekday().num_days_from_monday() as usize];
+            Some(Release {
+                time,
+                first_episode,
+                last_episode: *released,
+            })
+        })
+        .filter(|release| release.last_episode >= release.first_episode)
+        .map_while(move |release| match episodes_count {
+            0 => Some(release),
+            _ if release.first_episode > episodes_count => None,
+            _ => Some(Release {
+                last_episode: release.last_episode.min(episodes_count),
+                ..release
+            }),
+        }))
+}
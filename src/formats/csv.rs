@@ -0,0 +1,203 @@
+use super::SCHEMA_VERSION;
+use crate::model::ShowRecord;
+use anyhow::{bail, Context, Result};
+use std::{collections::HashMap, str::FromStr};
+
+pub const COLUMNS: [&str; 22] = [
+    "schema_version",
+    "id",
+    "title",
+    "alternative_title",
+    "release_date",
+    "about",
+    "link_to_show",
+    "score",
+    "favorite",
+    "status",
+    "show_type",
+    "season",
+    "episodes_count",
+    "episode",
+    "release_time",
+    "schedule_monday",
+    "schedule_tuesday",
+    "schedule_wednesday",
+    "schedule_thursday",
+    "schedule_friday",
+    "schedule_saturday",
+    "schedule_sunday",
+];
+
+/// Quotes a field when it contains a separator, a quote or a line break.
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn write_record<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    let fields = fields
+        .iter()
+        .map(|f| escape(f.as_ref()))
+        .collect::<Vec<String>>();
+    out.push_str(&fields.join(","));
+    out.push('\n');
+}
+
+pub fn to_string(shows: &[ShowRecord]) -> String {
+    let mut out = String::new();
+    write_record(&mut out, &COLUMNS);
+    for s in shows {
+        let mut fields = vec![
+            SCHEMA_VERSION.to_string(),
+            s.id.to_string(),
+            s.title.clone(),
+            s.alternative_title.clone(),
+            s.release_date.clone(),
+            s.about.clone(),
+            s.link_to_show.clone(),
+            s.score.to_string(),
+            s.favorite.to_string(),
+            s.status.to_string(),
+            s.show_type.to_string(),
+            s.season.to_string(),
+            s.episodes_count.to_string(),
+            s.episode.to_string(),
+            s.schedule.release_time.clone(),
+        ];
+        fields.extend(
+            s.schedule
+                .episodes_per_weekday
+                .iter()
+                .map(|n| n.to_string()),
+        );
+        write_record(&mut out, &fields);
+    }
+    out
+}
+
+/// Splits CSV content into records of fields, as described by RFC 4180. Blank lines are skipped.
+pub fn parse_records(content: &str) -> Result<Vec<Vec<String>>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            _ if quoted => field.push(c),
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' | '\n' => {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        bail!("Unterminated quoted field in CSV");
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.is_empty()) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Parses CSV with a header row into maps from column names to values.
+pub fn parse_table(content: &str) -> Result<Vec<HashMap<String, String>>> {
+    let mut records = parse_records(content)?.into_iter();
+    let header = match records.next() {
+        Some(header) => header,
+        None => return Ok(Vec::new()),
+    };
+    Ok(records
+        .map(|record| header.iter().cloned().zip(record).collect())
+        .collect())
+}
+
+/// Reads a text column verbatim, keeping the spaces and line breaks it was exported with.
+pub fn text(row: &HashMap<String, String>, column: &str) -> String {
+    row.get(column).cloned().unwrap_or_default()
+}
+
+/// Reads a numeric or enum column, which is allowed to be missing, empty or padded with spaces.
+pub fn field<T>(row: &HashMap<String, String>, column: &str) -> Result<T>
+where
+    T: FromStr + Default,
+    T::Err: std::fmt::Display,
+{
+    match row.get(column).map(|v| v.trim()) {
+        None | Some("") => Ok(T::default()),
+        Some(value) => value
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid {} \"{}\": {}", column, value, e)),
+    }
+}
+
+pub fn parse(content: &str) -> Result<Vec<ShowRecord>> {
+    parse_table(content)?
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            parse_show(row).with_context(|| format!("Failed to read CSV record {}", index + 1))
+        })
+        .collect()
+}
+
+fn parse_show(row: &HashMap<String, String>) -> Result<ShowRecord> {
+    let schema_version: u32 = field(row, "schema_version")?;
+    if schema_version > SCHEMA_VERSION {
+        bail!(
+            "The export has schema version {}, but only versions up to {} are supported",
+            schema_version,
+            SCHEMA_VERSION
+        );
+    }
+
+    let mut show = ShowRecord {
+        id: field(row, "id")?,
+        title: text(row, "title"),
+        alternative_title: text(row, "alternative_title"),
+        release_date: text(row, "release_date"),
+        about: text(row, "about"),
+        link_to_show: text(row, "link_to_show"),
+        score: field(row, "score")?,
+        favorite: field(row, "favorite")?,
+        status: field(row, "status")?,
+        show_type: field(row, "show_type")?,
+        season: field(row, "season")?,
+        episodes_count: field(row, "episodes_count")?,
+        episode: field(row, "episode")?,
+        ..Default::default()
+    };
+    show.schedule.release_time = text(row, "release_time");
+    for (count, column) in show
+        .schedule
+        .episodes_per_weekday
+        .iter_mut()
+        .zip(&COLUMNS[15..])
+    {
+        *count = field(row, column)?;
+    }
+    Ok(show)
+}
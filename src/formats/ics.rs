@@ -0,0 +1,150 @@
+use crate::{
+    datetime::{self, Release},
+    model::{ShowRecord, Status},
+};
+use chrono::{DateTime, Local, Utc};
+
+/// How far ahead episodes are written when the number of episodes of a show is unknown.
+pub const EXPANDED_WEEKS: i64 = 12;
+
+const WEEKDAYS: [&str; 7] = ["MO", "TU", "WE", "TH", "FR", "SA", "SU"];
+
+/// Release times are local to the user, so they are written as floating times rather than in UTC.
+const LOCAL_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line, folded to 75 octets as RFC 5545 asks.
+fn write_line(output: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            output.push_str("\r\n ");
+            length = 1;
+        }
+        output.push(c);
+        length += c.len_utf8();
+    }
+    output.push_str("\r\n");
+}
+
+fn episodes(release: &Release) -> String {
+    match release.first_episode == release.last_episode {
+        true => format!("episode {}", release.first_episode),
+        false => format!(
+            "episodes {}-{}",
+            release.first_episode, release.last_episode
+        ),
+    }
+}
+
+/// The releases of a show from now on, empty for shows without a valid schedule.
+fn upcoming_releases(show: &ShowRecord, now: &DateTime<Local>) -> Vec<Release> {
+    let releases = match datetime::releases(
+        &show.schedule.release_time,
+        show.schedule.episodes_per_weekday,
+        show.episodes_count.max(0) as u32,
+    ) {
+        Ok(releases) => releases,
+        Err(_) => return Vec::new(),
+    };
+
+    let now = now.naive_local();
+    let end = now + chrono::Duration::weeks(EXPANDED_WEEKS);
+    releases
+        .skip_while(|release| release.time < now)
+        .take_while(|release| show.episodes_count > 0 || release.time < end)
+        .collect()
+}
+
+/// One event repeating weekly on the release days, ending with the last episode if it is known.
+fn write_recurring(output: &mut String, show: &ShowRecord, releases: &[Release]) {
+    let weekdays = WEEKDAYS
+        .iter()
+        .zip(show.schedule.episodes_per_weekday)
+        .filter(|(_, episodes)| *episodes > 0)
+        .map(|(weekday, _)| *weekday)
+        .collect::<Vec<_>>();
+    let count = match show.episodes_count > 0 {
+        true => format!(";COUNT={}", releases.len()),
+        false => String::new(),
+    };
+
+    write_line(output, &format!("UID:watchlist-{}@watchlist", show.id));
+    write_line(
+        output,
+        &format!("DTSTART:{}", releases[0].time.format(LOCAL_TIME_FORMAT)),
+    );
+    write_line(
+        output,
+        &format!("RRULE:FREQ=WEEKLY;BYDAY={}{}", weekdays.join(","), count),
+    );
+    write_line(output, &format!("SUMMARY:{}", escape(&show.title)));
+}
+
+fn write_release(output: &mut String, show: &ShowRecord, release: &Release) {
+    write_line(
+        output,
+        &format!(
+            "UID:watchlist-{}-{}@watchlist",
+            show.id, release.first_episode
+        ),
+    );
+    write_line(
+        output,
+        &format!("DTSTART:{}", release.time.format(LOCAL_TIME_FORMAT)),
+    );
+    write_line(
+        output,
+        &format!(
+            "SUMMARY:{}",
+            escape(&format!("{}: {}", show.title, episodes(release)))
+        ),
+    );
+}
+
+/// A calendar of the upcoming episodes of the shows being watched. `expanded` writes an event for
+/// every release instead of one repeating event per show.
+pub fn to_string(shows: &[ShowRecord], expanded: bool, now: DateTime<Local>) -> String {
+    let stamp = format!(
+        "DTSTAMP:{}",
+        now.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")
+    );
+    let mut output = String::new();
+    write_line(&mut output, "BEGIN:VCALENDAR");
+    write_line(&mut output, "VERSION:2.0");
+    write_line(&mut output, "PRODID:-//watchlist//watchlist//EN");
+    write_line(&mut output, "X-WR-CALNAME:Watchlist");
+
+    for show in shows.iter().filter(|s| s.status == Status::Watching) {
+        let releases = upcoming_releases(show, &now);
+        if releases.is_empty() {
+            continue;
+        }
+
+        match expanded {
+            true => {
+                for release in &releases {
+                    write_line(&mut output, "BEGIN:VEVENT");
+                    write_release(&mut output, show, release);
+                    write_line(&mut output, &stamp);
+                    write_line(&mut output, "END:VEVENT");
+                }
+            }
+            false => {
+                write_line(&mut output, "BEGIN:VEVENT");
+                write_recurring(&mut output, show, &releases);
+                write_line(&mut output, &stamp);
+                write_line(&mut output, "END:VEVENT");
+            }
+        }
+    }
+
+    write_line(&mut output, "END:VCALENDAR");
+    output
+}
@@ -0,0 +1,74 @@
+use super::csv::{field, parse_table};
+use crate::model::{ShowRecord, ShowType, Status};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+const TITLE_LINK: &str = "https://www.imdb.com/title/";
+
+/// Tells the "Your Ratings" export of IMDb from other CSV.
+pub fn is_header(columns: &[String]) -> bool {
+    columns.iter().any(|c| c == "Const") && columns.iter().any(|c| c == "Your Rating")
+}
+
+fn is_episode(title_type: &str) -> bool {
+    matches!(title_type, "TV Episode" | "tvEpisode")
+}
+
+fn is_serial(title_type: &str) -> bool {
+    matches!(
+        title_type,
+        "TV Series" | "TV Mini Series" | "tvSeries" | "tvMiniSeries"
+    )
+}
+
+fn parse_row(row: &HashMap<String, String>) -> Result<ShowRecord> {
+    let title: String = field(row, "Title")?;
+    let original_title: String = field(row, "Original Title")?;
+    let mut release_date: String = field(row, "Release Date")?;
+    if release_date.is_empty() {
+        release_date = field(row, "Year")?;
+    }
+    let mut link: String = field(row, "URL")?;
+    let id: String = field(row, "Const")?;
+    if link.is_empty() && !id.is_empty() {
+        link = format!("{}{}/", TITLE_LINK, id);
+    }
+    let is_serial = is_serial(&field::<String>(row, "Title Type")?);
+
+    Ok(ShowRecord {
+        alternative_title: match original_title != title {
+            true => original_title,
+            false => String::new(),
+        },
+        title,
+        release_date,
+        link_to_show: link,
+        score: field::<i32>(row, "Your Rating")?.clamp(0, 10),
+        status: Status::Completed,
+        show_type: match is_serial {
+            true => ShowType::Serial,
+            false => ShowType::Film,
+        },
+        season: 1,
+        // The number of episodes of a serial is not in the export.
+        episodes_count: if is_serial { 0 } else { 1 },
+        episode: if is_serial { 0 } else { 1 },
+        ..Default::default()
+    })
+}
+
+/// Reads rated titles. Ratings of single episodes are skipped, as the list holds whole shows.
+pub fn parse(content: &str) -> Result<Vec<ShowRecord>> {
+    let mut shows = Vec::new();
+    for (index, row) in parse_table(content)?.iter().enumerate() {
+        if is_episode(&field::<String>(row, "Title Type")?) {
+            continue;
+        }
+        let show =
+            parse_row(row).with_context(|| format!("Failed to read IMDb record {}", index + 1))?;
+        if !show.title.is_empty() {
+            shows.push(show);
+        }
+    }
+    Ok(shows)
+}
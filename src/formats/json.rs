@@ -0,0 +1,39 @@
+use super::SCHEMA_VERSION;
+use crate::model::ShowRecord;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Export {
+    pub schema_version: u32,
+    pub shows: Vec<ExportedShow>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportedShow {
+    #[serde(flatten)]
+    pub show: ShowRecord,
+    /// The full picture in base64, only when pictures are exported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+}
+
+pub fn to_string(shows: &[ExportedShow]) -> Result<String> {
+    let export = Export {
+        schema_version: SCHEMA_VERSION,
+        shows: shows.to_vec(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+pub fn parse(content: &str) -> Result<Vec<ExportedShow>> {
+    let export: Export = serde_json::from_str(content).context("Failed to parse JSON export")?;
+    if export.schema_version > SCHEMA_VERSION {
+        bail!(
+            "The export has schema version {}, but only versions up to {} are supported",
+            export.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(export.shows)
+}
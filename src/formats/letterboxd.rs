@@ -0,0 +1,56 @@
+use super::csv::{field, parse_table};
+use crate::model::{ShowRecord, ShowType, Status};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+/// Tells the `diary.csv`, `ratings.csv` and `watched.csv` of a Letterboxd export from other CSV.
+pub fn is_header(columns: &[String]) -> bool {
+    columns.iter().any(|c| c == "Letterboxd URI")
+}
+
+/// Letterboxd rates from half a star to five stars.
+fn score(rating: f32) -> i32 {
+    ((rating * 2.0).round() as i32).clamp(0, 10)
+}
+
+fn parse_row(row: &HashMap<String, String>) -> Result<ShowRecord> {
+    Ok(ShowRecord {
+        title: field(row, "Name")?,
+        release_date: field(row, "Year")?,
+        link_to_show: field(row, "Letterboxd URI")?,
+        score: score(field(row, "Rating")?),
+        status: Status::Completed,
+        show_type: ShowType::Film,
+        season: 1,
+        episodes_count: 1,
+        episode: 1,
+        ..Default::default()
+    })
+}
+
+/// Reads watched films. A film logged several times in the diary is read once, with the values
+/// of the latest entry.
+pub fn parse(content: &str) -> Result<Vec<ShowRecord>> {
+    let mut shows: Vec<ShowRecord> = Vec::new();
+    for (index, row) in parse_table(content)?.iter().enumerate() {
+        let show = parse_row(row)
+            .with_context(|| format!("Failed to read Letterboxd record {}", index + 1))?;
+        if show.title.is_empty() {
+            continue;
+        }
+
+        match shows.iter_mut().find(|s| s.is_titled(&show.title)) {
+            Some(logged) => {
+                // Rewatches logged without a rating keep the earlier one.
+                let score = if show.score > 0 {
+                    show.score
+                } else {
+                    logged.score
+                };
+                *logged = ShowRecord { score, ..show };
+            }
+            None => shows.push(show),
+        }
+    }
+    Ok(shows)
+}
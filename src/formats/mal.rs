@@ -0,0 +1,136 @@
+use crate::model::{ShowRecord, ShowType, Status};
+use anyhow::{bail, Context, Result};
+
+const ANIME_LINK: &str = "https://myanimelist.net/anime/";
+
+/// `my_status` is written as text by MyAnimeList, but some tools write the numeric codes.
+fn parse_status(value: &str) -> Status {
+    match value.trim().to_lowercase().as_str() {
+        "1" | "watching" => Status::Watching,
+        "2" | "completed" => Status::Completed,
+        "4" | "dropped" => Status::Dropped,
+        // On hold and plan to watch.
+        _ => Status::WatchLater,
+    }
+}
+
+fn status_name(show: &ShowRecord) -> &'static str {
+    match show.status {
+        Status::Watching => "Watching",
+        Status::Completed => "Completed",
+        Status::Dropped => "Dropped",
+        Status::WatchLater if show.episode > 0 => "On-Hold",
+        Status::WatchLater => "Plan to Watch",
+    }
+}
+
+/// The MyAnimeList id, when the show was linked to its MyAnimeList page.
+pub fn anime_id(link: &str) -> Option<u64> {
+    link.strip_prefix(ANIME_LINK)?
+        .split('/')
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+fn child_text(node: roxmltree::Node, name: &str) -> String {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .unwrap_or_default()
+        .trim()
+        .to_owned()
+}
+
+fn child_number(node: roxmltree::Node, name: &str) -> Result<i32> {
+    let value = child_text(node, name);
+    match value.as_str() {
+        "" => Ok(0),
+        _ => value
+            .parse()
+            .with_context(|| format!("Invalid {} \"{}\"", name, value)),
+    }
+}
+
+fn parse_anime(anime: roxmltree::Node) -> Result<ShowRecord> {
+    let id = child_text(anime, "series_animedb_id");
+    Ok(ShowRecord {
+        title: child_text(anime, "series_title"),
+        link_to_show: match id.as_str() {
+            "" | "0" => String::new(),
+            _ => format!("{}{}", ANIME_LINK, id),
+        },
+        score: child_number(anime, "my_score")?.clamp(0, 10),
+        status: parse_status(&child_text(anime, "my_status")),
+        show_type: ShowType::Anime,
+        season: 1,
+        episodes_count: child_number(anime, "series_episodes")?,
+        episode: child_number(anime, "my_watched_episodes")?,
+        ..Default::default()
+    })
+}
+
+pub fn parse(content: &str) -> Result<Vec<ShowRecord>> {
+    let document =
+        roxmltree::Document::parse(content).context("Failed to parse MyAnimeList XML")?;
+    let root = document.root_element();
+    if !root.has_tag_name("myanimelist") {
+        bail!(
+            "Not a MyAnimeList export: the root element is <{}>",
+            root.tag_name().name()
+        );
+    }
+
+    root.children()
+        .filter(|n| n.has_tag_name("anime"))
+        .map(|anime| {
+            parse_anime(anime).with_context(|| {
+                format!(
+                    "Failed to read anime \"{}\"",
+                    child_text(anime, "series_title")
+                )
+            })
+        })
+        .collect()
+}
+
+/// Writes the anime of the list in the format MyAnimeList accepts for import. Shows not linked
+/// to MyAnimeList get id 0 and have to be matched by title there.
+pub fn to_string(shows: &[ShowRecord]) -> String {
+    let anime = shows
+        .iter()
+        .filter(|s| s.show_type == ShowType::Anime)
+        .collect::<Vec<_>>();
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n<myanimelist>\n");
+    out.push_str("\t<myinfo>\n");
+    out.push_str("\t\t<user_export_type>1</user_export_type>\n");
+    out.push_str(&format!(
+        "\t\t<user_total_anime>{}</user_total_anime>\n",
+        anime.len()
+    ));
+    out.push_str("\t</myinfo>\n");
+    for show in anime {
+        out.push_str("\t<anime>\n");
+        let mut element = |name: &str, value: String| {
+            out.push_str(&format!("\t\t<{0}>{1}</{0}>\n", name, value));
+        };
+        element(
+            "series_animedb_id",
+            anime_id(&show.link_to_show).unwrap_or_default().to_string(),
+        );
+        element("series_title", cdata(&show.title));
+        element("series_episodes", show.episodes_count.to_string());
+        element("my_watched_episodes", show.episode.to_string());
+        element("my_score", show.score.to_string());
+        element("my_status", status_name(show).to_owned());
+        element("update_on_import", "1".to_owned());
+        out.push_str("\t</anime>\n");
+    }
+    out.push_str("</myanimelist>\n");
+    out
+}
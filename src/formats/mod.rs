@@ -0,0 +1,212 @@
+pub mod csv;
+pub mod ics;
+pub mod imdb;
+pub mod json;
+pub mod letterboxd;
+pub mod mal;
+pub mod trakt;
+
+use crate::{database::Repository, import::IncomingShow};
+use anyhow::{bail, Context, Error, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use json::ExportedShow;
+use std::{fmt, path::Path, str::FromStr};
+
+/// Version of the JSON and CSV layouts, bumped on every incompatible change of either.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+    /// The `animelist.xml` of MyAnimeList, only holding anime.
+    MyAnimeList,
+    /// The diary, ratings or watched films of a Letterboxd export, import only.
+    Letterboxd,
+    /// The "Your Ratings" CSV of IMDb, import only.
+    ImdbRatings,
+    /// The JSON files of a Trakt backup, import only.
+    Trakt,
+}
+
+/// Shows read from a file, and descriptions of the entries that could not be imported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Parsed {
+    pub shows: Vec<IncomingShow>,
+    pub unmatched: Vec<String>,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Result<Format> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(extension) => extension.parse(),
+            None => bail!("Failed to guess the format of {}", path.display()),
+        }
+    }
+
+    /// Like `from_path`, but also tells the files of other sites from ours by their content.
+    /// A directory is taken for a Trakt backup.
+    pub fn detect(path: &Path, content: &str) -> Result<Format> {
+        if path.is_dir() {
+            return Ok(Format::Trakt);
+        }
+        let format = Format::from_path(path)?;
+        if format == Format::Json && content.trim_start().starts_with('[') {
+            return Ok(Format::Trakt);
+        }
+        if format != Format::Csv {
+            return Ok(format);
+        }
+
+        let header = csv::parse_records(content.lines().next().unwrap_or_default())?
+            .pop()
+            .unwrap_or_default();
+        if letterboxd::is_header(&header) {
+            Ok(Format::Letterboxd)
+        } else if imdb::is_header(&header) {
+            Ok(Format::ImdbRatings)
+        } else {
+            Ok(Format::Csv)
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::MyAnimeList => "mal",
+            Format::Letterboxd => "letterboxd",
+            Format::ImdbRatings => "imdb",
+            Format::Trakt => "trakt",
+        })
+    }
+}
+
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            "mal" | "xml" => Ok(Format::MyAnimeList),
+            "letterboxd" => Ok(Format::Letterboxd),
+            "imdb" => Ok(Format::ImdbRatings),
+            "trakt" => Ok(Format::Trakt),
+            _ => bail!("Unknown format \"{}\"", s),
+        }
+    }
+}
+
+/// Exports the whole watchlist, or only its anime to MyAnimeList. Pictures are only written to
+/// JSON, encoded in base64.
+pub fn export(repository: &Repository, format: Format, include_pictures: bool) -> Result<String> {
+    let mut shows = repository.load_shows()?;
+    // Ordered by id rather than as displayed, so that two exports of a list can be diffed.
+    shows.sort_by_key(|s| s.id);
+
+    match format {
+        Format::Json => {
+            let shows = shows
+                .into_iter()
+                .map(|show| {
+                    let picture = match include_pictures {
+                        true => repository
+                            .read_picture(show.id)?
+                            .map(|content| BASE64_STANDARD.encode(content)),
+                        false => None,
+                    };
+                    Ok(ExportedShow { show, picture })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            json::to_string(&shows)
+        }
+        Format::Csv => Ok(csv::to_string(&shows)),
+        Format::MyAnimeList => Ok(mal::to_string(&shows)),
+        Format::Letterboxd | Format::ImdbRatings | Format::Trakt => {
+            bail!("Exporting to {} is not supported", format)
+        }
+    }
+}
+
+/// Exports the watchlist to a file, in the format given by its extension.
+pub fn export_to_file(repository: &Repository, path: &Path, include_pictures: bool) -> Result<()> {
+    let content = export(repository, Format::from_path(path)?, include_pictures)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// A calendar of the upcoming episodes of the shows being watched, see `ics::to_string`.
+pub fn export_calendar(repository: &Repository, expanded: bool) -> Result<String> {
+    Ok(ics::to_string(
+        &repository.load_shows()?,
+        expanded,
+        chrono::Local::now(),
+    ))
+}
+
+pub fn export_calendar_to_file(repository: &Repository, path: &Path, expanded: bool) -> Result<()> {
+    let content = export_calendar(repository, expanded)?;
+    std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads shows exported in any of the formats. A Trakt backup is read from one of its files.
+pub fn parse(content: &str, format: Format) -> Result<Parsed> {
+    let shows = match format {
+        Format::Json => {
+            let shows = json::parse(content)?
+                .into_iter()
+                .map(|exported| {
+                    let picture = match exported.picture {
+                        Some(picture) => {
+                            Some(BASE64_STANDARD.decode(picture).with_context(|| {
+                                format!("Invalid picture of \"{}\"", exported.show.title)
+                            })?)
+                        }
+                        None => None,
+                    };
+                    Ok(IncomingShow {
+                        show: exported.show,
+                        picture,
+                        ..Default::default()
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Parsed {
+                shows,
+                ..Default::default()
+            });
+        }
+        Format::Trakt => return trakt::parse(&[content.to_owned()]),
+        Format::Csv => csv::parse(content)?,
+        Format::MyAnimeList => mal::parse(content)?,
+        Format::Letterboxd => letterboxd::parse(content)?,
+        Format::ImdbRatings => imdb::parse(content)?,
+    };
+    Ok(Parsed {
+        shows: shows
+            .into_iter()
+            .map(|show| IncomingShow {
+                show,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Reads a file in the given format, or the one detected from it. A Trakt backup is read from
+/// all the files of its directory, given the directory or any of the files.
+pub fn read(path: &Path, format: Option<Format>) -> Result<Parsed> {
+    if path.is_dir() || format == Some(Format::Trakt) {
+        return trakt::read(path);
+    }
+
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    match format.map_or_else(|| Format::detect(path, &content), Ok)? {
+        Format::Trakt => trakt::read(path),
+        format => parse(&content, format),
+    }
+}
@@ -0,0 +1,343 @@
+use super::Parsed;
+use crate::{
+    database::{WatchEvent, WATCHED_AT_FORMAT},
+    import::IncomingShow,
+    model::{ShowRecord, ShowType, Status},
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Ids {
+    trakt: Option<u64>,
+    slug: Option<String>,
+    imdb: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Media {
+    title: Option<String>,
+    year: Option<i32>,
+    ids: Ids,
+    aired_episodes: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WatchedEpisode {
+    number: i32,
+    last_watched_at: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WatchedSeason {
+    number: i32,
+    episodes: Vec<WatchedEpisode>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct EpisodeRef {
+    season: i32,
+    number: i32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SeasonRef {
+    number: i32,
+}
+
+/// An entry of any of the backup files. Which fields are set tells the files apart: ratings have
+/// `rating`, the watchlist `listed_at`, the history `watched_at` and watched shows `plays`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Entry {
+    show: Option<Media>,
+    movie: Option<Media>,
+    plays: Option<i32>,
+    last_watched_at: Option<String>,
+    seasons: Vec<WatchedSeason>,
+    rating: Option<i32>,
+    listed_at: Option<String>,
+    watched_at: Option<String>,
+    season: Option<SeasonRef>,
+    episode: Option<EpisodeRef>,
+}
+
+impl Entry {
+    fn media(&self) -> Option<(&Media, ShowType)> {
+        match (&self.show, &self.movie) {
+            (Some(show), _) => Some((show, ShowType::Serial)),
+            (None, Some(movie)) => Some((movie, ShowType::Film)),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Trakt writes UTC timestamps, the history keeps local time.
+fn local_time(timestamp: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|t| {
+        t.with_timezone(&Local)
+            .format(WATCHED_AT_FORMAT)
+            .to_string()
+    })
+}
+
+fn describe(media: &Media) -> String {
+    match (&media.title, media.year) {
+        (Some(title), Some(year)) => format!("\"{}\" ({})", title, year),
+        (Some(title), None) => format!("\"{}\"", title),
+        (None, _) => format!("untitled entry {}", media.ids.trakt.unwrap_or_default()),
+    }
+}
+
+fn link(media: &Media, show_type: ShowType) -> String {
+    match (&media.ids.imdb, &media.ids.slug) {
+        (Some(imdb), _) => format!("https://www.imdb.com/title/{}/", imdb),
+        (None, Some(slug)) if show_type == ShowType::Film => {
+            format!("https://trakt.tv/movies/{}", slug)
+        }
+        (None, Some(slug)) => format!("https://trakt.tv/shows/{}", slug),
+        (None, None) => String::new(),
+    }
+}
+
+struct Backup {
+    shows: Vec<(Option<u64>, IncomingShow)>,
+    unmatched: Vec<String>,
+}
+
+impl Backup {
+    /// Finds the show of an entry by its Trakt id or title, or adds it when `create` is set.
+    fn show(
+        &mut self,
+        media: &Media,
+        show_type: ShowType,
+        create: bool,
+    ) -> Option<&mut IncomingShow> {
+        let title = media.title.clone().unwrap_or_default();
+        let position = self.shows.iter().position(|(id, incoming)| {
+            (id.is_some() && *id == media.ids.trakt) || incoming.show.is_titled(&title)
+        });
+        let position = match position {
+            Some(position) => position,
+            None if create && !title.is_empty() => {
+                let show = ShowRecord {
+                    title,
+                    release_date: media.year.map(|y| y.to_string()).unwrap_or_default(),
+                    link_to_show: link(media, show_type),
+                    show_type,
+                    season: 1,
+                    ..Default::default()
+                };
+                self.shows.push((
+                    media.ids.trakt,
+                    IncomingShow {
+                        show,
+                        ..Default::default()
+                    },
+                ));
+                self.shows.len() - 1
+            }
+            None => return None,
+        };
+        Some(&mut self.shows[position].1)
+    }
+
+    fn add_event(&mut self, media: &Media, show_type: ShowType, event: WatchEvent) {
+        if let Some(incoming) = self.show(media, show_type, true) {
+            incoming.history.push(event);
+        }
+    }
+
+    fn add_watched(&mut self, entry: &Entry, media: &Media, show_type: ShowType) {
+        if show_type == ShowType::Film {
+            let event = WatchEvent {
+                season: 1,
+                episode: 1,
+                watched_at: entry.last_watched_at.as_deref().and_then(local_time),
+                ..Default::default()
+            };
+            return self.add_event(media, show_type, event);
+        }
+
+        for season in &entry.seasons {
+            for episode in &season.episodes {
+                let event = WatchEvent {
+                    season: season.number,
+                    episode: episode.number,
+                    watched_at: episode.last_watched_at.as_deref().and_then(local_time),
+                    ..Default::default()
+                };
+                self.add_event(media, show_type, event);
+            }
+        }
+        if let (Some(incoming), Some(aired)) =
+            (self.show(media, show_type, true), media.aired_episodes)
+        {
+            let watched = entry
+                .seasons
+                .iter()
+                .map(|s| s.episodes.len())
+                .sum::<usize>();
+            if aired > 0 && watched >= aired as usize {
+                incoming.show.status = Status::Completed;
+            }
+        }
+    }
+
+    fn add_rating(&mut self, entry: &Entry, media: &Media, show_type: ShowType, rating: i32) {
+        let description = describe(media);
+        let incoming = match self.show(media, show_type, false) {
+            Some(incoming) => incoming,
+            None => {
+                self.unmatched.push(format!(
+                    "Rating of {} has no watched or watchlist entry",
+                    description
+                ));
+                return;
+            }
+        };
+
+        match (&entry.season, &entry.episode) {
+            (_, Some(episode)) => {
+                let event = incoming
+                    .history
+                    .iter_mut()
+                    .rev()
+                    .find(|e| (e.season, e.episode) == (episode.season, episode.number));
+                match event {
+                    Some(event) => event.rating = Some(rating),
+                    None => self.unmatched.push(format!(
+                        "Rating of S{}E{} of {} has no watched episode",
+                        episode.season, episode.number, description
+                    )),
+                }
+            }
+            (Some(season), None) => self.unmatched.push(format!(
+                "Rating of season {} of {} is not imported, only shows and episodes are rated",
+                season.number, description
+            )),
+            (None, None) => incoming.show.score = rating.clamp(0, 10),
+        }
+    }
+
+    /// Sets the progress and status from the history, now that all of it is read.
+    fn finish(&mut self) {
+        for (_, incoming) in &mut self.shows {
+            // Events without a time keep the order of the file, before the dated ones. The
+            // same play is in both the watched shows and the history.
+            incoming
+                .history
+                .sort_by(|a, b| a.watched_at.cmp(&b.watched_at));
+            incoming.history.dedup_by(|e, previous| {
+                (e.season, e.episode, &e.watched_at)
+                    == (previous.season, previous.episode, &previous.watched_at)
+            });
+            match incoming.history.last() {
+                Some(last) => {
+                    incoming.show.season = last.season;
+                    incoming.show.episode = last.episode;
+                    if incoming.show.status == Status::WatchLater {
+                        incoming.show.status = match incoming.show.show_type {
+                            ShowType::Film => Status::Completed,
+                            _ => Status::Watching,
+                        };
+                    }
+                    if incoming.show.show_type == ShowType::Film {
+                        incoming.show.episodes_count = 1;
+                    }
+                }
+                None => incoming.show.status = Status::WatchLater,
+            }
+        }
+    }
+}
+
+/// Reads the files of a Trakt backup. Entries of files that are not about watching, such as
+/// lists or comments, are ignored.
+pub fn parse(contents: &[String]) -> Result<Parsed> {
+    let mut entries = Vec::new();
+    for content in contents {
+        let file: Vec<Entry> =
+            serde_json::from_str(content).context("Failed to parse Trakt JSON")?;
+        entries.extend(file);
+    }
+
+    let mut backup = Backup {
+        shows: Vec::new(),
+        unmatched: Vec::new(),
+    };
+    for entry in entries.iter().filter(|e| e.rating.is_none()) {
+        let (media, show_type) = match entry.media() {
+            Some(media) => media,
+            None => continue,
+        };
+
+        if entry.listed_at.is_some() {
+            backup.show(media, show_type, true);
+        } else if let Some(watched_at) = &entry.watched_at {
+            let (season, episode) = match (&entry.episode, show_type) {
+                (Some(episode), _) => (episode.season, episode.number),
+                (None, ShowType::Film) => (1, 1),
+                (None, _) => continue,
+            };
+            let event = WatchEvent {
+                season,
+                episode,
+                watched_at: local_time(watched_at),
+                ..Default::default()
+            };
+            backup.add_event(media, show_type, event);
+        } else if entry.plays.is_some() || entry.last_watched_at.is_some() {
+            backup.add_watched(entry, media, show_type);
+        }
+    }
+    backup.finish();
+
+    // Ratings refer to the shows and episodes of the other files.
+    for entry in &entries {
+        if let (Some((media, show_type)), Some(rating)) = (entry.media(), entry.rating) {
+            backup.add_rating(entry, media, show_type, rating);
+        }
+    }
+
+    Ok(Parsed {
+        shows: backup.shows.into_iter().map(|(_, show)| show).collect(),
+        unmatched: backup.unmatched,
+    })
+}
+
+/// The JSON files of a backup: those in a directory, or next to one of them.
+fn backup_files(path: &Path) -> Result<Vec<PathBuf>> {
+    let directory = match path.is_dir() {
+        true => path,
+        false => path.parent().unwrap_or(Path::new(".")),
+    };
+    let mut files = std::fs::read_dir(directory)
+        .with_context(|| format!("Failed to read directory {}", directory.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "json"))
+        .collect::<Vec<_>>();
+    files.sort();
+    Ok(files)
+}
+
+pub fn read(path: &Path) -> Result<Parsed> {
+    let mut contents = Vec::new();
+    for file in backup_files(path)? {
+        let content = std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        // Only arrays of entries belong to the backup, settings and profiles are objects.
+        if content.trim_start().starts_with('[') {
+            contents.push(content);
+        }
+    }
+    parse(&contents)
+}
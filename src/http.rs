@@ -1,5 +1,6 @@
 use anyhow::Result;
 use downloader::{Download, Downloader};
+use std::fmt;
 
 pub fn download_image_by_http(url: &std::path::Path) -> Result<std::path::PathBuf> {
     let mut p = std::env::temp_dir();
@@ -15,13 +16,105 @@ pub fn download_image_by_http(url: &std::path::Path) -> Result<std::path::PathBu
     let result = d.download(&[dl]).unwrap_or_default();
 
     for r in result {
-        match r {
-            Err(e) => print!("Error: {}", e),
-            Ok(s) => print!("Success: {}", &s),
+        if let Err(e) = r {
+            eprintln!("Error: {}", e);
         }
     }
 
-    Ok(std::path::PathBuf::from(
-        &p.join(url.file_name().unwrap_or_default()),
-    ))
+    Ok(download_path(url.to_str().unwrap_or_default()))
+}
+
+/// Where [`download_image_by_http`] saves the picture of a link.
+pub fn download_path(url: &str) -> std::path::PathBuf {
+    let file_name = std::path::Path::new(url).file_name().unwrap_or_default();
+    std::env::temp_dir().join("watchlist").join(file_name)
+}
+
+/// A request answered with an error status.
+#[derive(Debug)]
+pub struct StatusError {
+    /// Without the query, which may hold an API key.
+    pub url: String,
+    pub status: u32,
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Request to {} failed with status {}",
+            self.url, self.status
+        )
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Escapes text to be put in a link, such as a search query.
+pub fn encode(text: &str) -> String {
+    curl::easy::Easy::new().url_encode(text.as_bytes())
+}
+
+/// Asks for pages in a language, like `uk-UA`, when one is set.
+pub fn language_headers(language: Option<&str>) -> Vec<String> {
+    language
+        .map(|language| format!("Accept-Language: {}", language))
+        .into_iter()
+        .collect()
+}
+
+/// Reads a page or API response, failing on error statuses.
+pub fn get(url: &str, headers: &[String]) -> Result<String> {
+    let response = request(url, headers, None)?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+/// Downloads a file, such as a picture.
+pub fn get_bytes(url: &str) -> Result<Vec<u8>> {
+    request(url, &[], None)
+}
+
+/// Sends a JSON body, such as a GraphQL query, and reads the response.
+pub fn post_json(url: &str, body: &str) -> Result<String> {
+    let headers = [
+        "Content-Type: application/json".to_owned(),
+        "Accept: application/json".to_owned(),
+    ];
+    let response = request(url, &headers, Some(body))?;
+    Ok(String::from_utf8_lossy(&response).into_owned())
+}
+
+fn request(url: &str, headers: &[String], body: Option<&str>) -> Result<Vec<u8>> {
+    let mut list = curl::easy::List::new();
+    for header in headers {
+        list.append(header)?;
+    }
+
+    let mut response = Vec::new();
+    let mut easy = curl::easy::Easy::new();
+    easy.url(url)?;
+    if let Some(body) = body {
+        easy.post(true)?;
+        easy.post_fields_copy(body.as_bytes())?;
+    }
+    easy.http_headers(list)?;
+    easy.follow_location(true)?;
+    easy.useragent(concat!("watchlist/", env!("CARGO_PKG_VERSION")))?;
+    easy.connect_timeout(std::time::Duration::from_secs(5))?;
+    easy.timeout(std::time::Duration::from_secs(30))?;
+    {
+        let mut transfer = easy.transfer();
+        transfer.write_function(|data| {
+            response.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    let status = easy.response_code()?;
+    if status >= 400 {
+        let url = url.split('?').next().unwrap_or_default().to_owned();
+        return Err(StatusError { url, status }.into());
+    }
+    Ok(response)
 }
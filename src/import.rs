@@ -0,0 +1,354 @@
+use crate::{
+    database::{Repository, WatchEvent},
+    model::ShowRecord,
+};
+use anyhow::{bail, Error, Result};
+use std::{fmt, str::FromStr};
+
+/// A show read from a file, with the full picture and watch history if the file had them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IncomingShow {
+    pub show: ShowRecord,
+    pub picture: Option<Vec<u8>>,
+    pub history: Vec<WatchEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// There is no local show with this title.
+    New,
+    /// Importing would not change the local show.
+    Unchanged,
+    /// The incoming show only fills blanks or moves the progress forward.
+    Changed,
+    /// Both shows have different values for some fields.
+    Conflict,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Change::New => "new",
+            Change::Unchanged => "unchanged",
+            Change::Changed => "changed",
+            Change::Conflict => "conflict",
+        })
+    }
+}
+
+/// What to do with a show in conflict.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Resolution {
+    /// Leave the local show as it is.
+    #[default]
+    KeepLocal,
+    /// Take every field the incoming show has, including its progress.
+    TakeIncoming,
+    /// Keep the local fields but take the progress that is further along.
+    KeepNewerProgress,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [
+        Resolution::KeepLocal,
+        Resolution::TakeIncoming,
+        Resolution::KeepNewerProgress,
+    ];
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Resolution::KeepLocal => "keep-local",
+            Resolution::TakeIncoming => "take-incoming",
+            Resolution::KeepNewerProgress => "keep-newer-progress",
+        })
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep-local" => Ok(Resolution::KeepLocal),
+            "take-incoming" => Ok(Resolution::TakeIncoming),
+            "keep-newer-progress" => Ok(Resolution::KeepNewerProgress),
+            _ => bail!("Unknown resolution \"{}\"", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PreviewItem {
+    pub incoming: IncomingShow,
+    pub local: Option<ShowRecord>,
+    pub change: Change,
+    /// Fields set to different values in both shows.
+    pub conflicts: Vec<&'static str>,
+    /// Only used for conflicts.
+    pub resolution: Resolution,
+    /// Incoming watch events the local history lacks, which make an otherwise unchanged show
+    /// changed.
+    pub new_events: usize,
+}
+
+impl PreviewItem {
+    /// The show as it will be saved, `None` when the import leaves it alone.
+    pub fn result(&self) -> Option<ShowRecord> {
+        let local = match &self.local {
+            Some(local) => local,
+            None => {
+                return Some(ShowRecord {
+                    id: 0,
+                    season: self.incoming.show.season.max(1),
+                    ..self.incoming.show.clone()
+                });
+            }
+        };
+
+        let resolution = match self.change {
+            Change::Unchanged => return None,
+            Change::Changed => Resolution::KeepNewerProgress,
+            Change::Conflict if self.resolution == Resolution::KeepLocal => return None,
+            Change::New | Change::Conflict => self.resolution,
+        };
+        let incoming = &self.incoming.show;
+        let (mut show, _) = merge(local, incoming, resolution == Resolution::TakeIncoming);
+        if (resolution == Resolution::TakeIncoming && incoming.season > 0)
+            || progress(incoming) > progress(local)
+        {
+            show.season = incoming.season;
+            show.episode = incoming.episode;
+        }
+        Some(show)
+    }
+
+    /// Names of the fields that importing changes in the local show.
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        match (&self.local, self.result()) {
+            (Some(local), Some(result)) => {
+                let mut fields = differences(local, &result);
+                if self.new_events > 0 {
+                    fields.push("history");
+                }
+                fields
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+fn progress(show: &ShowRecord) -> (i32, i32) {
+    (show.season, show.episode)
+}
+
+fn merge_field<T: Clone + Default + PartialEq>(
+    name: &'static str,
+    local: &mut T,
+    incoming: &T,
+    incoming_wins: bool,
+    conflicts: &mut Vec<&'static str>,
+) {
+    if *incoming == T::default() || local == incoming {
+        return;
+    }
+    if *local != T::default() {
+        conflicts.push(name);
+        if !incoming_wins {
+            return;
+        }
+    }
+    *local = incoming.clone();
+}
+
+/// Fills the blanks of the local show with the incoming one. Fields set in both shows are
+/// conflicts, resolved by `incoming_wins`. Progress is left to the caller.
+fn merge(
+    local: &ShowRecord,
+    incoming: &ShowRecord,
+    incoming_wins: bool,
+) -> (ShowRecord, Vec<&'static str>) {
+    let mut show = local.clone();
+    let mut conflicts = Vec::new();
+    let c = &mut conflicts;
+    let w = incoming_wins;
+    merge_field(
+        "alternative title",
+        &mut show.alternative_title,
+        &incoming.alternative_title,
+        w,
+        c,
+    );
+    merge_field(
+        "release date",
+        &mut show.release_date,
+        &incoming.release_date,
+        w,
+        c,
+    );
+    merge_field("about", &mut show.about, &incoming.about, w, c);
+    merge_field("link", &mut show.link_to_show, &incoming.link_to_show, w, c);
+    merge_field("score", &mut show.score, &incoming.score, w, c);
+    merge_field("favorite", &mut show.favorite, &incoming.favorite, w, c);
+    merge_field("status", &mut show.status, &incoming.status, w, c);
+    merge_field("type", &mut show.show_type, &incoming.show_type, w, c);
+    merge_field(
+        "episodes",
+        &mut show.episodes_count,
+        &incoming.episodes_count,
+        w,
+        c,
+    );
+    merge_field(
+        "release time",
+        &mut show.schedule.release_time,
+        &incoming.schedule.release_time,
+        w,
+        c,
+    );
+    merge_field(
+        "schedule",
+        &mut show.schedule.episodes_per_weekday,
+        &incoming.schedule.episodes_per_weekday,
+        w,
+        c,
+    );
+    (show, conflicts)
+}
+
+fn differences(local: &ShowRecord, other: &ShowRecord) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    let mut diff = |name, is_different| {
+        if is_different {
+            fields.push(name);
+        }
+    };
+    diff(
+        "alternative title",
+        local.alternative_title != other.alternative_title,
+    );
+    diff("release date", local.release_date != other.release_date);
+    diff("about", local.about != other.about);
+    diff("link", local.link_to_show != other.link_to_show);
+    diff("score", local.score != other.score);
+    diff("favorite", local.favorite != other.favorite);
+    diff("status", local.status != other.status);
+    diff("type", local.show_type != other.show_type);
+    diff("episodes", local.episodes_count != other.episodes_count);
+    diff("progress", progress(local) != progress(other));
+    diff(
+        "release time",
+        local.schedule.release_time != other.schedule.release_time,
+    );
+    diff(
+        "schedule",
+        local.schedule.episodes_per_weekday != other.schedule.episodes_per_weekday,
+    );
+    fields
+}
+
+fn classify(local: &ShowRecord, incoming: &ShowRecord) -> (Change, Vec<&'static str>) {
+    let (merged, mut conflicts) = merge(local, incoming, false);
+    if incoming.season > 0 && progress(incoming) < progress(local) {
+        conflicts.push("progress");
+    }
+
+    if !conflicts.is_empty() {
+        (Change::Conflict, conflicts)
+    } else if merged == *local && progress(incoming) <= progress(local) {
+        (Change::Unchanged, conflicts)
+    } else {
+        (Change::Changed, conflicts)
+    }
+}
+
+/// Matches incoming shows with the local ones by title. Later shows with the title of an earlier
+/// one are dropped, so a file cannot add the same show twice.
+pub fn preview(repository: &Repository, incoming: Vec<IncomingShow>) -> Result<Vec<PreviewItem>> {
+    let shows = repository.load_shows()?;
+    let mut items: Vec<PreviewItem> = Vec::new();
+
+    for show in incoming {
+        if show.show.title.is_empty()
+            || items
+                .iter()
+                .any(|item| item.incoming.show.is_titled(&show.show.title))
+        {
+            continue;
+        }
+
+        let local = shows
+            .iter()
+            .find(|s| s.is_titled(&show.show.title))
+            .cloned();
+        let new_events = match &local {
+            Some(local) => {
+                let history = repository.watch_history(local.id)?;
+                show.history
+                    .iter()
+                    .filter(|event| !history.iter().any(|e| e.is_same_watch(event)))
+                    .count()
+            }
+            None => show.history.len(),
+        };
+        let (change, conflicts) = match &local {
+            Some(local) => match classify(local, &show.show) {
+                (Change::Unchanged, conflicts) if new_events > 0 => (Change::Changed, conflicts),
+                classified => classified,
+            },
+            None => (Change::New, Vec::new()),
+        };
+        items.push(PreviewItem {
+            incoming: show,
+            local,
+            change,
+            conflicts,
+            resolution: Resolution::default(),
+            new_events,
+        });
+    }
+    Ok(items)
+}
+
+/// Saves the previewed shows in one transaction.
+pub fn apply(repository: &Repository, items: &[PreviewItem]) -> Result<ImportSummary> {
+    repository.transaction(|| {
+        let mut summary = ImportSummary::default();
+        for item in items {
+            let show = match item.result() {
+                Some(show) => show,
+                None => {
+                    summary.skipped += 1;
+                    continue;
+                }
+            };
+
+            let id = repository.save_show(&show)?;
+            if let Some(picture) = &item.incoming.picture {
+                let replace = item.local.is_none()
+                    || item.resolution == Resolution::TakeIncoming
+                    || repository.read_picture(id)?.is_none();
+                if replace {
+                    repository.update_picture(id, picture)?;
+                }
+            }
+            if !item.incoming.history.is_empty() {
+                repository.import_watch_events(id, &item.incoming.history)?;
+            }
+
+            match item.local {
+                Some(_) => summary.updated += 1,
+                None => summary.added += 1,
+            }
+        }
+        Ok(summary)
+    })
+}
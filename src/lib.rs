@@ -0,0 +1,12 @@
+pub mod config;
+pub mod database;
+pub mod datetime;
+pub mod formats;
+pub mod http;
+pub mod import;
+pub mod model;
+pub mod parsers;
+pub mod refresh;
+pub mod sites;
+#[cfg(test)]
+mod tests;
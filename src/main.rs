@@ -1,128 +1,129 @@
-mod database;
-mod datetime;
-mod http;
-mod parsers;
-mod sites;
 #[cfg(test)]
+#[path = "tests/ui.rs"]
 mod tests;
+mod ui;
 
 use anyhow::Result;
 use chrono::{Datelike, Local, NaiveDateTime, TimeZone, Timelike};
-use database::*;
-use datetime::*;
-use http::*;
-use sites::*;
 use slint::{Model, ModelRc, VecModel};
-use std::{rc::Rc, sync::{Arc, Mutex}};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+use watchlist::{
+    config::{self, Config},
+    database::Repository,
+    formats::{self, Format},
+    http::*,
+    import::{self, PreviewItem, Resolution},
+    model::ShowRecord,
+    refresh::{self, RefreshItem},
+    sites::*,
+};
 
 slint::include_modules!();
 
 fn main() -> Result<()> {
-    database::create()?;
+    // Read once, the searches and refreshes on other threads share it.
+    let config = Arc::new(Config::load()?);
+    let repository = Rc::new(Repository::open(&config::database_path(
+        config::database_arg(std::env::args().skip(1)),
+        &config,
+    )?)?);
     let ui = AppWindow::new()?;
 
     let is_watchlist_loaded = Arc::new(Mutex::new(false));
     let is_watchlist_loaded_clone = is_watchlist_loaded.clone();
     let ui_weak = ui.as_weak();
+    let repository_clone = repository.clone();
     slint::Timer::single_shot(std::time::Duration::from_millis(10), move || {
-        database::load_watchlist(&ui_weak.unwrap(), is_watchlist_loaded_clone).unwrap();
+        ui::load_watchlist(
+            &ui_weak.unwrap(),
+            &repository_clone,
+            is_watchlist_loaded_clone,
+        )
+        .unwrap();
     });
 
     let is_watchlist_loaded_clone = is_watchlist_loaded.clone();
     let ui_weak = ui.as_weak();
     let check_new_episodes_timer = slint::Timer::default();
-    check_new_episodes_timer.start(slint::TimerMode::Repeated, std::time::Duration::from_secs(1), move || {
-        let is_watchlist_loaded = is_watchlist_loaded_clone.lock().unwrap();
-        if !*is_watchlist_loaded {
-            return;
-        }
-        let ui = ui_weak.unwrap();
-        let shows = ui.get_shows();
-
-        for i in 0..shows.row_count() {
-            let s = shows.row_data(i).unwrap();
-            let is_new_episodes_available = match check_new_episodes_available(
-                s.release_time.as_str(),
-                s.episode as u32,
-                [
-                    s.schedule_monday as u32,
-                    s.schedule_tuesday as u32,
-                    s.schedule_wednesday as u32,
-                    s.schedule_thursday as u32,
-                    s.schedule_friday as u32,
-                    s.schedule_saturday as u32,
-                    s.schedule_sunday as u32,
-                ],
-            ) {
-                Ok(v) => v,
-                Err(_) => false,
-            };
-
-            if s.new_episodes_available != is_new_episodes_available {
-                ui.invoke_change_new_episodes_available_status(i as i32, is_new_episodes_available);
-            };
-        }
-    });
-
-    ui.on_add_show(|shows, show| match add_show(&show) {
-        Ok(_) => {
-            let model = shows.as_any().downcast_ref::<VecModel<Show>>();
-            if let None = model {
-                eprintln!("Failed to downcast watchlist");
+    check_new_episodes_timer.start(
+        slint::TimerMode::Repeated,
+        std::time::Duration::from_secs(1),
+        move || {
+            let is_watchlist_loaded = is_watchlist_loaded_clone.lock().unwrap();
+            if !*is_watchlist_loaded {
                 return;
             }
-            let model = model.unwrap();
+            let ui = ui_weak.unwrap();
+            let shows = ui.get_shows();
 
-            if show.id == 0 {
-                let next_id = if shows.row_count() > 0 {
-                    shows.row_data(0).unwrap().id + 1
-                } else {
-                    1
-                };
-                let mut show = show.clone();
-                show.id = next_id;
-                show.new_episodes_available = match check_new_episodes_available(
-                    show.release_time.as_str(),
-                    show.episode as u32,
-                    [
-                        show.schedule_monday as u32,
-                        show.schedule_tuesday as u32,
-                        show.schedule_wednesday as u32,
-                        show.schedule_thursday as u32,
-                        show.schedule_friday as u32,
-                        show.schedule_saturday as u32,
-                        show.schedule_sunday as u32,
-                    ],
-                ) {
-                    Ok(v) => v,
-                    Err(_) => false,
+            for i in 0..shows.row_count() {
+                let s = shows.row_data(i).unwrap();
+                let is_new_episodes_available = ShowRecord::from(&s).new_episodes_available();
+
+                if s.new_episodes_available != is_new_episodes_available {
+                    ui.invoke_change_new_episodes_available_status(
+                        i as i32,
+                        is_new_episodes_available,
+                    );
                 };
-                let status = show.status;
-                let index = model
-                    .iter()
-                    .position(|v| v.status.eq(&status))
-                    .unwrap_or_default();
-                model.insert(index, show);
-            } else {
-                for i in 0..model.row_count() {
-                    let s = model.row_data(i).unwrap();
-                    if s.id == show.id {
-                        model.set_row_data(i, show.clone());
-                        break;
+            }
+        },
+    );
+
+    // The show last imported in the add dialog, to tell which fields were typed by hand.
+    let last_import = Arc::new(Mutex::new(None::<ShowRecord>));
+
+    let repository_clone = repository.clone();
+    let last_import_clone = last_import.clone();
+    ui.on_add_show(move |shows, show| {
+        match refresh::save_entered_show(
+            &repository_clone,
+            &ShowRecord::from(&show),
+            last_import_clone.lock().unwrap().as_ref(),
+        ) {
+            Ok(id) => {
+                let model = shows.as_any().downcast_ref::<VecModel<Show>>();
+                if model.is_none() {
+                    eprintln!("Failed to downcast watchlist");
+                    return;
+                }
+                let model = model.unwrap();
+
+                if show.id == 0 {
+                    let mut show = show.clone();
+                    show.id = id as i32;
+                    show.new_episodes_available = ShowRecord::from(&show).new_episodes_available();
+                    let status = show.status;
+                    let index = model
+                        .iter()
+                        .position(|v| v.status.eq(&status))
+                        .unwrap_or_default();
+                    model.insert(index, show);
+                } else {
+                    for i in 0..model.row_count() {
+                        let s = model.row_data(i).unwrap();
+                        if s.id == show.id {
+                            model.set_row_data(i, show.clone());
+                            break;
+                        }
                     }
                 }
-            }
 
-            let count = model.row_count();
-            for i in 0..count {
-                let mut s = model.row_data(i).unwrap();
-                s.index = i as i32;
-                model.set_row_data(i, s);
+                let count = model.row_count();
+                for i in 0..count {
+                    let mut s = model.row_data(i).unwrap();
+                    s.index = i as i32;
+                    model.set_row_data(i, s);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
             }
-        }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return;
         }
     });
 
@@ -145,62 +146,119 @@ fn main() -> Result<()> {
         }
     });
 
-    ui.on_remove_show(|shows, show| match remove_show(&show) {
-        Ok(_) => {
-            let model = shows.as_any().downcast_ref::<VecModel<Show>>();
-            if let None = model {
-                eprintln!("Failed to downcast watchlist");
-                return;
-            }
-            let model = model.unwrap();
-            model.remove(show.index as usize);
-            for i in 0..model.row_count() {
-                let mut s = model.row_data(i).unwrap();
-                s.index = i as i32;
-                model.set_row_data(i, s);
+    let repository_clone = repository.clone();
+    ui.on_load_picture(move |show| -> slint::Image {
+        match repository_clone
+            .read_picture(show.id as i64)
+            .and_then(|content| content.map(|c| ui::decode_picture(&c)).transpose())
+        {
+            Ok(Some(buffer)) => slint::Image::from_rgba8(buffer),
+            Ok(None) => show.picture,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                show.picture
             }
         }
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            return;
-        }
     });
 
-    ui.on_score_changed(|show| {
-        _ = score_changed(&show).map_err(|e| eprintln!("Error: {}", e));
+    let repository_clone = repository.clone();
+    ui.on_remove_show(
+        move |shows, show| match repository_clone.delete_show(show.id as i64) {
+            Ok(_) => {
+                let model = shows.as_any().downcast_ref::<VecModel<Show>>();
+                if model.is_none() {
+                    eprintln!("Failed to downcast watchlist");
+                    return;
+                }
+                let model = model.unwrap();
+                model.remove(show.index as usize);
+                for i in 0..model.row_count() {
+                    let mut s = model.row_data(i).unwrap();
+                    s.index = i as i32;
+                    model.set_row_data(i, s);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+            }
+        },
+    );
+
+    let repository_clone = repository.clone();
+    ui.on_score_changed(move |show| {
+        _ = repository_clone
+            .update_score(show.id as i64, show.score)
+            .map_err(|e| eprintln!("Error: {}", e));
+    });
+
+    let repository_clone = repository.clone();
+    ui.on_status_changed(move |show| {
+        _ = repository_clone
+            .update_status(show.id as i64, show.status.into())
+            .map_err(|e| eprintln!("Error: {}", e));
+    });
+
+    let repository_clone = repository.clone();
+    ui.on_favorite_changed(move |show| {
+        _ = repository_clone
+            .update_favorite(show.id as i64, show.favorite)
+            .map_err(|e| eprintln!("Error: {}", e));
     });
 
-    ui.on_status_changed(|show| {
-        _ = status_changed(&show).map_err(|e| eprintln!("Error: {}", e));
+    let repository_clone = repository.clone();
+    ui.on_season_changed(move |show| {
+        _ = repository_clone
+            .update_progress(show.id as i64, show.season, show.episode)
+            .map_err(|e| eprintln!("Error: {}", e));
     });
 
-    ui.on_favorite_changed(|show| {
-        _ = favorite_changed(&show).map_err(|e| eprintln!("Error: {}", e));
+    let repository_clone = repository.clone();
+    ui.on_episode_changed(move |show| {
+        _ = repository_clone
+            .update_progress(show.id as i64, show.season, show.episode)
+            .map_err(|e| eprintln!("Error: {}", e));
     });
 
-    ui.on_season_changed(|show| {
-        _ = season_changed(&show).map_err(|e| eprintln!("Error: {}", e));
+    let repository_clone = repository.clone();
+    ui.on_undo_progress(move |show| -> Show {
+        match repository_clone.undo_progress(show.id as i64) {
+            Ok((season, episode)) => {
+                let mut show = show.clone();
+                show.season = season;
+                show.episode = episode;
+                show.new_episodes_available = ShowRecord::from(&show).new_episodes_available();
+                show
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                show
+            }
+        }
     });
 
-    ui.on_episode_changed(|show| {
-        _ = episode_changed(&show).map_err(|e| eprintln!("Error: {}", e));
+    let repository_clone = repository.clone();
+    ui.on_get_watch_history(move |show| -> ModelRc<slint::SharedString> {
+        let history = repository_clone
+            .watch_history(show.id as i64)
+            .map_err(|e| eprintln!("Error: {}", e))
+            .unwrap_or_default();
+        let lines = history
+            .iter()
+            .rev()
+            .map(|event| {
+                slint::SharedString::from(format!(
+                    "S{}E{}  {}",
+                    event.season,
+                    event.episode,
+                    event.watched_at.as_deref().unwrap_or("unknown time")
+                ))
+            })
+            .collect::<Vec<_>>();
+        ModelRc::from(Rc::new(VecModel::from(lines)))
     });
 
     ui.on_check_new_episode_available(|show| -> bool {
-        check_new_episodes_available(
-            show.release_time.as_str(),
-            show.episode as u32,
-            [
-                show.schedule_monday as u32,
-                show.schedule_tuesday as u32,
-                show.schedule_wednesday as u32,
-                show.schedule_thursday as u32,
-                show.schedule_friday as u32,
-                show.schedule_saturday as u32,
-                show.schedule_sunday as u32,
-            ],
-        )
-        .unwrap_or_default()
+        ShowRecord::from(&show).new_episodes_available()
     });
 
     ui.on_open_link(|link| {
@@ -263,7 +321,49 @@ fn main() -> Result<()> {
     });
 
     ui.on_can_import_show_by_link(|link| check_link_is_importable(&link));
-    ui.on_import_clicked(|link| import_clicked(&link));
+    let config_clone = config.clone();
+    let ui_weak = ui.as_weak();
+    ui.on_import_clicked(move |link| {
+        ui::show_busy(&ui_weak.unwrap(), "Importing...");
+        let ui_weak = ui_weak.clone();
+        let config = config_clone.clone();
+        let last_import = last_import.clone();
+        thread::spawn(move || {
+            let result = fetch(&link, &config);
+            *last_import.lock().unwrap() =
+                result.as_ref().ok().map(|imported| imported.show.clone());
+            _ = slint::invoke_from_event_loop(move || {
+                if let Some(app) = ui_weak.upgrade() {
+                    ui::hide_busy(&app);
+                    app.invoke_show_import_result(ui::import_result(result));
+                }
+            });
+        });
+    });
+
+    let ui_weak = ui.as_weak();
+    let config_clone = config.clone();
+    ui.on_search_title(move |title| {
+        ui::show_busy(&ui_weak.unwrap(), "Searching...");
+        let ui_weak = ui_weak.clone();
+        let config = config_clone.clone();
+        thread::spawn(move || {
+            let (results, errors) = search(&title, &config);
+            for (site, e) in errors {
+                eprintln!("Error: {} search failed: {}", site, e);
+            }
+            let count = results.len();
+            let pictures =
+                ui::search_pictures(&results, |done| ui::report_progress(&ui_weak, done, count));
+            _ = slint::invoke_from_event_loop(move || {
+                if let Some(app) = ui_weak.upgrade() {
+                    let items = ui::search_items(&results, pictures);
+                    ui::hide_busy(&app);
+                    app.invoke_show_search_results(ModelRc::from(Rc::new(VecModel::from(items))));
+                }
+            });
+        });
+    });
 
     ui.on_get_weekday_now(|| Local::now().weekday() as i32);
 
@@ -287,7 +387,7 @@ fn main() -> Result<()> {
             chrono::offset::LocalResult::None => Default::default(),
         };
         ModelRc::from(Rc::new(VecModel::from(vec![
-            release_time.year() as i32,
+            release_time.year(),
             release_time.month() as i32,
             release_time.day() as i32,
             release_time.hour() as i32,
@@ -306,6 +406,190 @@ fn main() -> Result<()> {
         }
     });
 
+    let repository_clone = repository.clone();
+    ui.on_export_watchlist(move || {
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name("watchlist.json")
+            .add_filter("JSON", &["json"])
+            .add_filter("CSV", &["csv"])
+            .add_filter("MyAnimeList", &["xml"])
+            .add_filter("Calendar of upcoming episodes", &["ics"])
+            .save_file()
+        else {
+            return;
+        };
+        if path.extension().is_some_and(|e| e == "ics") {
+            let expanded = rfd::MessageDialog::new()
+                .set_title("Export calendar")
+                .set_description(
+                    "Add every episode as its own event? Otherwise each show repeats weekly.",
+                )
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show()
+                == rfd::MessageDialogResult::Yes;
+            if let Err(e) = formats::export_calendar_to_file(&repository_clone, &path, expanded) {
+                eprintln!("Error: {}", e);
+            }
+            return;
+        }
+        let include_pictures = matches!(Format::from_path(&path), Ok(Format::Json))
+            && rfd::MessageDialog::new()
+                .set_title("Export watchlist")
+                .set_description("Include pictures? They make the file much larger.")
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show()
+                == rfd::MessageDialogResult::Yes;
+        if let Err(e) = formats::export_to_file(&repository_clone, &path, include_pictures) {
+            eprintln!("Error: {}", e);
+        }
+    });
+
+    let pending_import = Rc::new(RefCell::new(Vec::<PreviewItem>::new()));
+    let pending_import_clone = pending_import.clone();
+    let ui_weak = ui.as_weak();
+    let repository_clone = repository.clone();
+    ui.on_preview_import(move || {
+        let parsed = match rfd::FileDialog::new()
+            .add_filter("Watchlist export", &["json", "csv", "xml"])
+            .pick_file()
+        {
+            Some(path) => formats::read(&path, None).and_then(|parsed| {
+                let items = import::preview(&repository_clone, parsed.shows)?;
+                Ok((items, parsed.unmatched))
+            }),
+            None => Ok(Default::default()),
+        };
+        let (items, unmatched) = parsed.unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            Default::default()
+        });
+
+        let unmatched = unmatched
+            .iter()
+            .map(|entry| slint::SharedString::from(entry.as_str()))
+            .collect::<Vec<slint::SharedString>>();
+        ui_weak
+            .unwrap()
+            .set_import_unmatched(ModelRc::from(Rc::new(VecModel::from(unmatched))));
+        let model = items
+            .iter()
+            .map(ui::import_item)
+            .collect::<Vec<ImportItem>>();
+        *pending_import_clone.borrow_mut() = items;
+        ModelRc::from(Rc::new(VecModel::from(model)))
+    });
+
+    let ui_weak = ui.as_weak();
+    let repository_clone = repository.clone();
+    let is_watchlist_loaded_clone = is_watchlist_loaded.clone();
+    ui.on_apply_import(move |model| {
+        let mut items = pending_import.take();
+        for (item, row) in items.iter_mut().zip(model.iter()) {
+            item.resolution = Resolution::ALL
+                .get(row.resolution as usize)
+                .copied()
+                .unwrap_or_default();
+        }
+        let result = import::apply(&repository_clone, &items).and_then(|_| {
+            *is_watchlist_loaded_clone.lock().unwrap() = false;
+            ui::load_watchlist(
+                &ui_weak.unwrap(),
+                &repository_clone,
+                is_watchlist_loaded_clone.clone(),
+            )
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+        }
+    });
+
+    // Pages and pictures are fetched on another thread, the results are saved on this one.
+    let pending_refresh = Arc::new(Mutex::new(Vec::<RefreshItem>::new()));
+    let pending_refresh_clone = pending_refresh.clone();
+    let ui_weak = ui.as_weak();
+    let repository_clone = repository.clone();
+    let config_clone = config.clone();
+    ui.on_preview_refresh(move |id| {
+        let shows = match id {
+            0 => refresh::watching(&repository_clone),
+            id => repository_clone.load_show(id as i64).map(|show| vec![show]),
+        };
+        let targets = match shows.and_then(|shows| refresh::targets(&repository_clone, &shows)) {
+            Ok(targets) => targets,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        };
+
+        let ui = ui_weak.unwrap();
+        ui.set_import_unmatched(ModelRc::from(Rc::new(
+            VecModel::<slint::SharedString>::default(),
+        )));
+        ui::show_busy(&ui, "Refreshing...");
+        let ui_weak = ui_weak.clone();
+        let pending_refresh = pending_refresh_clone.clone();
+        let config = config_clone.clone();
+        thread::spawn(move || {
+            let count = targets.len();
+            let items = refresh::fetch_changes(
+                targets,
+                |link| fetch(link, &config),
+                |done| ui::report_progress(&ui_weak, done, count),
+            );
+            let model = items
+                .iter()
+                .map(ui::refresh_item)
+                .collect::<Vec<ImportItem>>();
+            *pending_refresh.lock().unwrap() = items;
+            _ = slint::invoke_from_event_loop(move || {
+                if let Some(app) = ui_weak.upgrade() {
+                    ui::hide_busy(&app);
+                    app.invoke_show_refresh(ModelRc::from(Rc::new(VecModel::from(model))));
+                }
+            });
+        });
+    });
+
+    let downloaded_pictures = Arc::new(Mutex::new(Vec::<Option<refresh::Picture>>::new()));
+    let downloaded_pictures_clone = downloaded_pictures.clone();
+    let pending_refresh_clone = pending_refresh.clone();
+    let ui_weak = ui.as_weak();
+    ui.on_apply_refresh(move || {
+        let items = pending_refresh_clone.lock().unwrap().clone();
+        ui::show_busy(&ui_weak.unwrap(), "Downloading pictures...");
+        let ui_weak = ui_weak.clone();
+        let downloaded_pictures = downloaded_pictures_clone.clone();
+        thread::spawn(move || {
+            let count = items.len();
+            let pictures = refresh::download_pictures(&items, get_bytes, |done| {
+                ui::report_progress(&ui_weak, done, count)
+            });
+            *downloaded_pictures.lock().unwrap() = pictures;
+            _ = slint::invoke_from_event_loop(move || {
+                if let Some(app) = ui_weak.upgrade() {
+                    app.invoke_refresh_downloaded();
+                }
+            });
+        });
+    });
+
+    let ui_weak = ui.as_weak();
+    let repository_clone = repository.clone();
+    ui.on_refresh_downloaded(move || {
+        let items = std::mem::take(&mut *pending_refresh.lock().unwrap());
+        let pictures = std::mem::take(&mut *downloaded_pictures.lock().unwrap());
+        let ui = ui_weak.unwrap();
+        ui::hide_busy(&ui);
+        let result = refresh::save(&repository_clone, &items, &pictures).and_then(|_| {
+            *is_watchlist_loaded.lock().unwrap() = false;
+            ui::load_watchlist(&ui, &repository_clone, is_watchlist_loaded.clone())
+        });
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+        }
+    });
+
     ui.run()?;
     Ok(())
 }
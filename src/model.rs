@@ -0,0 +1,170 @@
+use crate::datetime::check_new_episodes_available;
+use anyhow::{bail, Error};
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Status {
+    #[default]
+    #[serde(alias = "planned")]
+    WatchLater,
+    Watching,
+    Completed,
+    Dropped,
+}
+
+impl Status {
+    pub fn to_db(self) -> i64 {
+        match self {
+            Status::WatchLater => 0,
+            Status::Watching => 1,
+            Status::Completed => 2,
+            Status::Dropped => 3,
+        }
+    }
+
+    pub fn from_db(value: i64) -> Status {
+        match value {
+            1 => Status::Watching,
+            2 => Status::Completed,
+            3 => Status::Dropped,
+            _ => Status::WatchLater,
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::WatchLater => "watch-later",
+            Status::Watching => "watching",
+            Status::Completed => "completed",
+            Status::Dropped => "dropped",
+        })
+    }
+}
+
+impl FromStr for Status {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "watch-later" | "planned" => Ok(Status::WatchLater),
+            "watching" => Ok(Status::Watching),
+            "completed" => Ok(Status::Completed),
+            "dropped" => Ok(Status::Dropped),
+            _ => bail!("Unknown status \"{}\"", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShowType {
+    #[default]
+    Serial,
+    Film,
+    Cartoon,
+    Anime,
+}
+
+impl ShowType {
+    pub fn to_db(self) -> i64 {
+        match self {
+            ShowType::Serial => 0,
+            ShowType::Film => 1,
+            ShowType::Cartoon => 2,
+            ShowType::Anime => 3,
+        }
+    }
+
+    pub fn from_db(value: i64) -> ShowType {
+        match value {
+            1 => ShowType::Film,
+            2 => ShowType::Cartoon,
+            3 => ShowType::Anime,
+            _ => ShowType::Serial,
+        }
+    }
+}
+
+impl fmt::Display for ShowType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShowType::Serial => "serial",
+            ShowType::Film => "film",
+            ShowType::Cartoon => "cartoon",
+            ShowType::Anime => "anime",
+        })
+    }
+}
+
+impl FromStr for ShowType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "serial" => Ok(ShowType::Serial),
+            "film" => Ok(ShowType::Film),
+            "cartoon" => Ok(ShowType::Cartoon),
+            "anime" => Ok(ShowType::Anime),
+            _ => bail!("Unknown show type \"{}\"", s),
+        }
+    }
+}
+
+/// When episodes come out: the first release in `%Y-%m-%d %H:%M` local time and how many
+/// episodes are released on each weekday, starting from Monday.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Schedule {
+    pub release_time: String,
+    pub episodes_per_weekday: [u32; 7],
+}
+
+impl Schedule {
+    pub fn new_episodes_available(&self, current_episode: u32) -> bool {
+        check_new_episodes_available(
+            &self.release_time,
+            current_episode,
+            self.episodes_per_weekday,
+        )
+        .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShowRecord {
+    /// 0 for a show that is not stored yet.
+    pub id: i64,
+    pub title: String,
+    pub alternative_title: String,
+    pub release_date: String,
+    pub about: String,
+    pub link_to_show: String,
+    /// A local path or URL of a new picture, empty to keep the stored one.
+    #[serde(skip)]
+    pub link_to_picture: String,
+    pub score: i32,
+    pub favorite: bool,
+    pub status: Status,
+    pub show_type: ShowType,
+    pub season: i32,
+    pub episodes_count: i32,
+    pub episode: i32,
+    pub schedule: Schedule,
+}
+
+impl ShowRecord {
+    pub fn new_episodes_available(&self) -> bool {
+        self.schedule.new_episodes_available(self.episode as u32)
+    }
+
+    /// Compares the title and alternative title, ignoring case.
+    pub fn is_titled(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        self.title.to_lowercase() == title || self.alternative_title.to_lowercase() == title
+    }
+}
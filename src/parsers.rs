@@ -1,7 +1,6 @@
-use crate::Show;
-use anyhow::{Context, Result};
+use crate::model::ShowRecord;
+use anyhow::Result;
 use regex::Regex;
-use webpage::{Webpage, WebpageOptions};
 
 #[derive(Default)]
 struct ParsedShow {
@@ -12,10 +11,12 @@ struct ParsedShow {
     link_to_picture: String,
 }
 
-fn replace_html_entities(text: &str) -> String {
+pub fn replace_html_entities(text: &str) -> String {
     let mut result = text.to_owned();
     result = result.replace("&#x27;", "'");
     result = result.replace("&#039;", "'");
+    result = result.replace("&#39;", "'");
+    result = result.replace("&apos;", "'");
     result = result.replace("&quot;", "\"");
     result = result.replace("&amp;", "&");
     result = result.replace("&lt;", "<");
@@ -24,50 +25,56 @@ fn replace_html_entities(text: &str) -> String {
     result
 }
 
-fn scrab(
+/// Plain text of an HTML description, line breaks kept.
+pub fn html_to_text(html: &str) -> Result<String> {
+    let breaks = Regex::new(r"(?i)<br\s*/?>\n?")?;
+    let tags = Regex::new(r"<[^>]+>")?;
+    let text = breaks.replace_all(html, "\n");
+    Ok(replace_html_entities(tags.replace_all(&text, "").trim()))
+}
+
+/// Reads the fields of a show from a page, each with a regex capturing it in its first group.
+pub fn scrab(
+    doc: &str,
     link: &str,
     title: &str,
     alternative: &str,
     release: &str,
     about: &str,
     image: &str,
-) -> Result<Show> {
-    let site = Webpage::from_url(link, WebpageOptions::default())
-        .with_context(|| format!("Could not read from URL: {}", link))?;
-    let doc = site.http.body;
-
+) -> Result<ShowRecord> {
     let mut parsed = ParsedShow::default();
 
     let re_title = Regex::new(title)?;
-    if let Some(captures) = re_title.captures(&doc) {
+    if let Some(captures) = re_title.captures(doc) {
         if let Some(text) = captures.get(1) {
             parsed.title = replace_html_entities(text.as_str());
         }
     }
 
     let re_alternative = Regex::new(alternative)?;
-    if let Some(captures) = re_alternative.captures(&doc) {
+    if let Some(captures) = re_alternative.captures(doc) {
         if let Some(text) = captures.get(1) {
             parsed.alternative_title = replace_html_entities(text.as_str());
         }
     }
 
     let re_release = Regex::new(release)?;
-    if let Some(captures) = re_release.captures(&doc) {
+    if let Some(captures) = re_release.captures(doc) {
         if let Some(text) = captures.get(1) {
             parsed.release_date = replace_html_entities(text.as_str());
         }
     }
 
     let re_about = Regex::new(about)?;
-    if let Some(captures) = re_about.captures(&doc) {
+    if let Some(captures) = re_about.captures(doc) {
         if let Some(text) = captures.get(1) {
             parsed.about = replace_html_entities(text.as_str());
         }
     }
 
     let re_image = Regex::new(image)?;
-    if let Some(captures) = re_image.captures(&doc) {
+    if let Some(captures) = re_image.captures(doc) {
         if let Some(text) = captures.get(1) {
             parsed.link_to_picture = replace_html_entities(text.as_str());
         }
@@ -77,36 +84,13 @@ fn scrab(
         parsed.alternative_title = parsed.title.clone();
     }
 
-    Ok(Show {
-        title: parsed.title.into(),
-        alternative_title: parsed.alternative_title.into(),
-        release_date: parsed.release_date.into(),
-        about: parsed.about.into(),
-        link_to_picture: parsed.link_to_picture.into(),
-        link_to_show: link.into(),
+    Ok(ShowRecord {
+        title: parsed.title,
+        alternative_title: parsed.alternative_title,
+        release_date: parsed.release_date,
+        about: parsed.about,
+        link_to_picture: parsed.link_to_picture,
+        link_to_show: link.to_owned(),
         ..Default::default()
     })
 }
-
-fn get_show(parsed: Result<Show>) -> Show {
-    match parsed {
-        Ok(p) => p,
-        Err(e) => {
-            eprintln!("Error: {}", e);
-            Show::default()
-        }
-    }
-}
-
-pub fn imdb(link: &str) -> Show {
-    let parsed = scrab(
-        link,
-        r#"hero__primary-text">([^<]+)"#,
-        r#"Original title: ([^<]+)"#,
-        r#"releaseinfo\?ref_=tt_ov_rdat">([^<]+)"#,
-        r#"bruFve">([^<]+)"#,
-        r#"class="ipc-image" loading="eager" src="([^"]+)"#,
-    );
-
-    get_show(parsed)
-}
@@ -0,0 +1,333 @@
+use crate::{
+    database::Repository,
+    http,
+    model::{ShowRecord, Status},
+    sites::{ImportError, ImportedShow},
+};
+use anyhow::Result;
+use std::fmt;
+
+/// Fields a show takes from its page, besides the picture, named as in the preview.
+pub const FIELDS: [&str; 5] = [
+    "alternative title",
+    "release date",
+    "about",
+    "episodes",
+    "schedule",
+];
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// A field the page of a show has another value for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub local: String,
+    pub fetched: String,
+    /// The field was typed by hand, so the local value is kept.
+    pub edited: bool,
+}
+
+/// The first words of a value, on one line.
+fn short(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(40) {
+        _ if text.is_empty() => "none".to_owned(),
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+impl fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} → {}",
+            self.field,
+            short(&self.local),
+            short(&self.fetched)
+        )?;
+        if self.edited {
+            f.write_str(" (edited by hand, kept)")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshItem {
+    pub local: ShowRecord,
+    pub fetched: Result<ShowRecord, ImportError>,
+    pub changes: Vec<FieldChange>,
+}
+
+impl RefreshItem {
+    /// The show with the changes of the fields not edited by hand, `None` when nothing changes.
+    pub fn result(&self) -> Option<ShowRecord> {
+        let fetched = self.fetched.as_ref().ok()?;
+        let mut show = self.local.clone();
+        let mut changed = false;
+        for change in self.changes.iter().filter(|change| !change.edited) {
+            take(&mut show, fetched, change.field);
+            changed = true;
+        }
+        changed.then_some(show)
+    }
+
+    /// The link to the new picture of the show.
+    pub fn picture(&self) -> Option<&str> {
+        self.changes
+            .iter()
+            .find(|change| change.field == "picture" && !change.edited)
+            .map(|change| change.fetched.as_str())
+    }
+}
+
+fn schedule(show: &ShowRecord) -> String {
+    let days = show
+        .schedule
+        .episodes_per_weekday
+        .iter()
+        .zip(DAYS)
+        .filter(|(count, _)| **count > 0)
+        .map(|(count, day)| match count {
+            1 => day.to_owned(),
+            _ => format!("{} ×{}", day, count),
+        })
+        .collect::<Vec<_>>();
+    match days.is_empty() {
+        true => String::new(),
+        false => format!("{} from {}", days.join(", "), show.schedule.release_time),
+    }
+}
+
+/// The value of a field as shown in the preview, empty when the show has none.
+fn value(show: &ShowRecord, field: &str) -> String {
+    match field {
+        "alternative title" => show.alternative_title.clone(),
+        "release date" => show.release_date.clone(),
+        "about" => show.about.clone(),
+        "episodes" if show.episodes_count > 0 => show.episodes_count.to_string(),
+        "schedule" => schedule(show),
+        "picture" => show.link_to_picture.clone(),
+        _ => String::new(),
+    }
+}
+
+fn take(show: &mut ShowRecord, fetched: &ShowRecord, field: &str) {
+    match field {
+        "alternative title" => show.alternative_title = fetched.alternative_title.clone(),
+        "release date" => show.release_date = fetched.release_date.clone(),
+        "about" => show.about = fetched.about.clone(),
+        "episodes" => show.episodes_count = fetched.episodes_count,
+        "schedule" => show.schedule = fetched.schedule.clone(),
+        _ => {}
+    }
+}
+
+/// Names of the fields with different values in both shows.
+pub fn differences(show: &ShowRecord, other: &ShowRecord) -> Vec<&'static str> {
+    FIELDS
+        .into_iter()
+        .filter(|field| value(show, field) != value(other, field))
+        .collect()
+}
+
+/// What refreshing the local show from the fetched one changes. Fields the page has no value
+/// for are left alone. `picture_url` is where the local picture was downloaded from, see
+/// [`Repository::picture_url`].
+pub fn compare(
+    local: &ShowRecord,
+    picture_url: Option<&str>,
+    fetched: &ShowRecord,
+    edited: &[String],
+) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    for field in FIELDS.into_iter().chain(["picture"]) {
+        let local_value = match (field, picture_url) {
+            ("picture", Some(url)) => url.to_owned(),
+            ("picture", None) => continue,
+            _ => value(local, field),
+        };
+        let fetched_value = value(fetched, field);
+        if fetched_value.is_empty() || fetched_value == local_value {
+            continue;
+        }
+        changes.push(FieldChange {
+            field,
+            local: local_value,
+            fetched: fetched_value,
+            edited: edited.iter().any(|edited| edited == field),
+        });
+    }
+    changes
+}
+
+/// The shows refreshed all at once, the ones being watched.
+pub fn watching(repository: &Repository) -> Result<Vec<ShowRecord>> {
+    Ok(repository
+        .load_shows()?
+        .into_iter()
+        .filter(|show| show.status == Status::Watching)
+        .collect())
+}
+
+/// A show to refresh with what the database knows of its fields, read before the pages are
+/// fetched so that fetching needs no database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefreshTarget {
+    pub show: ShowRecord,
+    pub picture_url: Option<String>,
+    pub edited: Vec<String>,
+}
+
+/// The shows that have a link, to be fetched with [`fetch_changes`].
+pub fn targets(repository: &Repository, shows: &[ShowRecord]) -> Result<Vec<RefreshTarget>> {
+    shows
+        .iter()
+        .filter(|show| !show.link_to_show.is_empty())
+        .map(|show| {
+            Ok(RefreshTarget {
+                show: show.clone(),
+                picture_url: repository.picture_url(show.id)?,
+                edited: repository.edited_fields(show.id)?,
+            })
+        })
+        .collect()
+}
+
+/// Fetches the page of every target and compares it with the show, calling `progress` with the
+/// number of pages fetched so far.
+pub fn fetch_changes(
+    targets: Vec<RefreshTarget>,
+    fetch: impl Fn(&str) -> Result<ImportedShow, ImportError>,
+    mut progress: impl FnMut(usize),
+) -> Vec<RefreshItem> {
+    let mut items = Vec::new();
+    for target in targets {
+        let fetched = fetch(&target.show.link_to_show).map(|imported| imported.show);
+        let changes = match &fetched {
+            Ok(fetched) => compare(
+                &target.show,
+                target.picture_url.as_deref(),
+                fetched,
+                &target.edited,
+            ),
+            Err(_) => Vec::new(),
+        };
+        items.push(RefreshItem {
+            local: target.show,
+            fetched,
+            changes,
+        });
+        progress(items.len());
+    }
+    items
+}
+
+/// Fetches the pages of the shows that have a link, and compares them with the shows.
+pub fn preview(
+    repository: &Repository,
+    shows: &[ShowRecord],
+    fetch: impl Fn(&str) -> Result<ImportedShow, ImportError>,
+) -> Result<Vec<RefreshItem>> {
+    Ok(fetch_changes(targets(repository, shows)?, fetch, |_| {}))
+}
+
+/// A new picture of a show, with the link it was downloaded from.
+pub type Picture = (String, Vec<u8>);
+
+/// Downloads the new pictures of the refreshed shows, `None` for the shows that keep theirs or
+/// whose picture failed to download. `progress` gets the number of shows done so far.
+pub fn download_pictures(
+    items: &[RefreshItem],
+    download: impl Fn(&str) -> Result<Vec<u8>>,
+    mut progress: impl FnMut(usize),
+) -> Vec<Option<Picture>> {
+    let mut pictures = Vec::new();
+    for item in items {
+        let picture = item.picture().and_then(|url| match download(url) {
+            Ok(content) => Some((url.to_owned(), content)),
+            Err(e) => {
+                eprintln!(
+                    "Failed to download the picture of \"{}\": {}",
+                    item.local.title, e
+                );
+                None
+            }
+        });
+        pictures.push(picture);
+        progress(pictures.len());
+    }
+    pictures
+}
+
+/// Downloads the new pictures and saves the refreshed shows, see [`save`].
+pub fn apply(
+    repository: &Repository,
+    items: &[RefreshItem],
+    download: impl Fn(&str) -> Result<Vec<u8>>,
+) -> Result<usize> {
+    let pictures = download_pictures(items, download, |_| {});
+    save(repository, items, &pictures)
+}
+
+/// Saves the refreshed shows in one transaction, with the pictures of [`download_pictures`].
+/// Returns how many shows were updated.
+pub fn save(
+    repository: &Repository,
+    items: &[RefreshItem],
+    pictures: &[Option<Picture>],
+) -> Result<usize> {
+    repository.transaction(|| {
+        let mut updated = 0;
+        for (item, picture) in items.iter().zip(pictures) {
+            let Some(show) = item.result() else {
+                continue;
+            };
+            repository.save_show(&show)?;
+            if let Some((url, content)) = picture {
+                repository.update_picture(show.id, content)?;
+                repository.update_picture_url(show.id, url)?;
+            }
+            updated += 1;
+        }
+        Ok(updated)
+    })
+}
+
+/// Saves a show from the add or edit dialog, remembering which fields were typed by hand so
+/// refreshing keeps them. `imported` is the show last imported in the dialog. A picture other
+/// than the one downloaded from the import is kept too.
+pub fn save_entered_show(
+    repository: &Repository,
+    show: &ShowRecord,
+    imported: Option<&ShowRecord>,
+) -> Result<i64> {
+    let imported = imported.filter(|imported| {
+        show.id == 0 && !show.link_to_show.is_empty() && imported.link_to_show == show.link_to_show
+    });
+    let downloaded = imported
+        .map(|imported| imported.link_to_picture.as_str())
+        .filter(|url| {
+            !url.is_empty()
+                && !show.link_to_picture.is_empty()
+                && std::path::Path::new(&show.link_to_picture) == http::download_path(url)
+        });
+    repository.transaction(|| {
+        let mut edited = match imported {
+            _ if show.id != 0 => differences(&repository.load_show(show.id)?, show),
+            Some(imported) => differences(imported, show),
+            None => differences(&ShowRecord::default(), show),
+        };
+        if !show.link_to_picture.is_empty() && downloaded.is_none() {
+            edited.push("picture");
+        }
+        let id = repository.save_show(show)?;
+        repository.mark_edited(id, &edited)?;
+        if let Some(url) = downloaded {
+            repository.update_picture_url(id, url)?;
+        }
+        Ok(id)
+    })
+}
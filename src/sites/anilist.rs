@@ -0,0 +1,259 @@
+use super::{ImportedShow, SearchResult, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{Schedule, ShowRecord, ShowType},
+    parsers,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Local};
+use serde::Deserialize;
+
+const API: &str = "https://graphql.anilist.co";
+
+const QUERY: &str = "query ($id: Int) {
+  Media(id: $id, type: ANIME) {
+    title { romaji english native }
+    description
+    episodes
+    status
+    format
+    coverImage { extraLarge large }
+    nextAiringEpisode { airingAt episode }
+    startDate { year }
+    endDate { year }
+  }
+}";
+
+const SEARCH_QUERY: &str = "query ($search: String) {
+  Page(perPage: 10) {
+    media(search: $search, type: ANIME) {
+      title { romaji english }
+      format
+      siteUrl
+      coverImage { medium }
+      startDate { year }
+    }
+  }
+}";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Title {
+    romaji: Option<String>,
+    english: Option<String>,
+    native: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct CoverImage {
+    extra_large: Option<String>,
+    large: Option<String>,
+    medium: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AiringEpisode {
+    airing_at: i64,
+    episode: i32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Date {
+    year: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Media {
+    title: Title,
+    description: Option<String>,
+    episodes: Option<i32>,
+    status: String,
+    /// Like `TV` or `MOVIE`.
+    format: Option<String>,
+    site_url: String,
+    cover_image: CoverImage,
+    next_airing_episode: Option<AiringEpisode>,
+    start_date: Date,
+    end_date: Date,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct Data {
+    media: Option<Media>,
+    page: Option<Page>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Page {
+    media: Vec<Media>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Error {
+    message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Response {
+    data: Option<Data>,
+    errors: Vec<Error>,
+}
+
+pub struct AniList;
+
+/// The id of an anime page, like `https://anilist.co/anime/178754/Kaijuu-8gou/`.
+pub fn anime_id(url: &str) -> Option<u64> {
+    if !super::is_on_domain(url, "anilist.co") {
+        return None;
+    }
+    match super::path_segments(url)[..] {
+        ["anime", id, ..] => id.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Films are told apart from the other formats, which are all anime.
+fn show_type(format: Option<&str>) -> ShowType {
+    match format {
+        Some("MOVIE") => ShowType::Film,
+        _ => ShowType::Anime,
+    }
+}
+
+/// Counts back from the next episode to when the first one aired, taking one episode a week.
+fn schedule(next: &AiringEpisode) -> Schedule {
+    let first = DateTime::from_timestamp(next.airing_at, 0)
+        .map(|t| t - chrono::Duration::weeks(next.episode.max(1) as i64 - 1))
+        .map(|t| t.with_timezone(&Local));
+    match first {
+        Some(first) => {
+            let mut episodes_per_weekday = [0; 7];
+            episodes_per_weekday[first.weekday().num_days_from_monday() as usize] = 1;
+            Schedule {
+                release_time: first.format("%Y-%m-%d %H:%M").to_string(),
+                episodes_per_weekday,
+            }
+        }
+        None => Schedule::default(),
+    }
+}
+
+/// Reads a response of the GraphQL API to `QUERY`, titled in the language when AniList has a
+/// title in it.
+pub fn parse(content: &str, link: &str, language: Option<&str>) -> Result<ImportedShow> {
+    let response: Response =
+        serde_json::from_str(content).context("Failed to parse the AniList response")?;
+    if let Some(error) = response.errors.first() {
+        bail!("AniList failed to find {}: {}", link, error.message);
+    }
+    let Some(media) = response.data.and_then(|d| d.media) else {
+        bail!("AniList has no anime for {}", link);
+    };
+    let Some(romaji) = media
+        .title
+        .romaji
+        .as_deref()
+        .or(media.title.english.as_deref())
+    else {
+        bail!("AniList has no title for {}", link);
+    };
+    let (title, alternative_title) = super::anime_titles(
+        romaji,
+        media.title.english.as_deref(),
+        media.title.native.as_deref(),
+        language,
+    );
+
+    let release_date = match (media.start_date.year, media.end_date.year) {
+        (Some(start), Some(end)) if start != end && media.status == "FINISHED" => {
+            format!("{}–{}", start, end)
+        }
+        (Some(start), _) => start.to_string(),
+        (None, _) => String::new(),
+    };
+    Ok(ShowRecord {
+        title,
+        alternative_title,
+        release_date,
+        about: parsers::html_to_text(media.description.as_deref().unwrap_or_default())?,
+        link_to_show: link.to_owned(),
+        link_to_picture: media
+            .cover_image
+            .extra_large
+            .or(media.cover_image.large)
+            .unwrap_or_default(),
+        show_type: show_type(media.format.as_deref()),
+        episodes_count: media.episodes.unwrap_or_default(),
+        schedule: media
+            .next_airing_episode
+            .as_ref()
+            .map(schedule)
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+    .into())
+}
+
+/// Reads a response of the GraphQL API to `SEARCH_QUERY`.
+pub fn parse_search(content: &str) -> Result<Vec<SearchResult>> {
+    let response: Response =
+        serde_json::from_str(content).context("Failed to parse the AniList search response")?;
+    if let Some(error) = response.errors.first() {
+        bail!("AniList failed to search: {}", error.message);
+    }
+    let found = response
+        .data
+        .and_then(|d| d.page)
+        .map(|page| page.media)
+        .unwrap_or_default();
+    Ok(found
+        .into_iter()
+        .filter_map(|media| {
+            Some(SearchResult {
+                title: media.title.romaji.or(media.title.english)?,
+                year: media
+                    .start_date
+                    .year
+                    .map(|year| year.to_string())
+                    .unwrap_or_default(),
+                show_type: show_type(media.format.as_deref()),
+                link: media.site_url,
+                picture: media.cover_image.medium.unwrap_or_default(),
+                ..Default::default()
+            })
+        })
+        .collect())
+}
+
+impl SiteParser for AniList {
+    fn name(&self) -> &'static str {
+        "AniList"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        anime_id(url).is_some()
+    }
+
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow> {
+        let Some(id) = anime_id(url) else {
+            bail!("{} is not an AniList anime", url);
+        };
+        let body = serde_json::json!({ "query": QUERY, "variables": { "id": id } });
+        let content = http::post_json(API, &body.to_string())?;
+        parse(&content, url, config.language(self.name()))
+    }
+
+    fn search(&self, title: &str, _config: &Config) -> Result<Vec<SearchResult>> {
+        let body = serde_json::json!({ "query": SEARCH_QUERY, "variables": { "search": title } });
+        parse_search(&http::post_json(API, &body.to_string())?)
+    }
+}
@@ -0,0 +1,147 @@
+use super::{ImportedShow, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{ShowRecord, ShowType},
+    parsers,
+};
+use anyhow::{bail, Result};
+use serde_json::Value;
+use webpage::HTML;
+
+/// Any other page, read from its schema.org data or OpenGraph tags. Tried after every site.
+pub struct Generic;
+
+/// A text, or the first of a list of them.
+pub fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) if !text.trim().is_empty() => {
+            Some(parsers::replace_html_entities(text.trim()))
+        }
+        Value::Array(values) => values.iter().find_map(text),
+        _ => None,
+    }
+}
+
+/// An image is a link, an `ImageObject` with one, or a list of either.
+pub fn image(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("url").and_then(text),
+        Value::Array(values) => values.iter().find_map(image),
+        _ => text(value),
+    }
+}
+
+fn year(date: &str) -> &str {
+    date.split('-').next().unwrap_or_default()
+}
+
+fn show_type(schema_type: &str, genres: &[String]) -> ShowType {
+    if schema_type == "Movie" {
+        ShowType::Film
+    } else if genres.iter().any(|g| g.eq_ignore_ascii_case("anime")) {
+        ShowType::Anime
+    } else if genres.iter().any(|g| g.eq_ignore_ascii_case("animation")) {
+        ShowType::Cartoon
+    } else {
+        ShowType::Serial
+    }
+}
+
+/// The show of a `TVSeries` or `Movie` object in JSON-LD.
+fn schema_org(html: &HTML) -> Option<ShowRecord> {
+    let item = html
+        .schema_org
+        .iter()
+        .find(|item| matches!(item.schema_type.as_str(), "TVSeries" | "Movie"))?;
+    let value = &item.value;
+    let genres = match &value["genre"] {
+        Value::Array(genres) => genres.iter().filter_map(text).collect(),
+        genre => text(genre).into_iter().collect::<Vec<_>>(),
+    };
+
+    Some(ShowRecord {
+        title: text(&value["name"])?,
+        alternative_title: text(&value["alternateName"]).unwrap_or_default(),
+        release_date: text(&value["startDate"])
+            .or_else(|| text(&value["datePublished"]))
+            .map(|date| year(&date).to_owned())
+            .unwrap_or_default(),
+        about: text(&value["description"]).unwrap_or_default(),
+        link_to_picture: image(&value["image"]).unwrap_or_default(),
+        show_type: show_type(&item.schema_type, &genres),
+        episodes_count: value["numberOfEpisodes"]
+            .as_i64()
+            .or_else(|| value["numberOfEpisodes"].as_str()?.parse().ok())
+            .unwrap_or_default() as i32,
+        ..Default::default()
+    })
+}
+
+/// The show of the `og:` meta tags, or the title and description of the page.
+fn opengraph(html: &HTML) -> Option<ShowRecord> {
+    let properties = &html.opengraph.properties;
+    let title = properties
+        .get("title")
+        .or(html.title.as_ref())
+        .filter(|t| !t.trim().is_empty())?;
+    Some(ShowRecord {
+        title: title.trim().to_owned(),
+        about: properties
+            .get("description")
+            .or(html.description.as_ref())
+            .map(|d| d.trim().to_owned())
+            .unwrap_or_default(),
+        link_to_picture: html
+            .opengraph
+            .images
+            .first()
+            .map(|i| i.url.clone())
+            .unwrap_or_default(),
+        show_type: match html.opengraph.og_type.as_str() {
+            "video.movie" => ShowType::Film,
+            _ => ShowType::Serial,
+        },
+        ..Default::default()
+    })
+}
+
+/// Reads a show from a parsed page, preferring schema.org data, and filling what it lacks from
+/// OpenGraph.
+pub fn parse(html: &HTML, link: &str) -> Result<ImportedShow> {
+    let opengraph = opengraph(html);
+    let mut show = match (schema_org(html), &opengraph) {
+        (Some(show), _) => show,
+        (None, Some(show)) => show.clone(),
+        (None, None) => bail!("Found no show on {}", link),
+    };
+    if let Some(opengraph) = opengraph {
+        if show.about.is_empty() {
+            show.about = opengraph.about;
+        }
+        if show.link_to_picture.is_empty() {
+            show.link_to_picture = opengraph.link_to_picture;
+        }
+    }
+    if show.alternative_title.is_empty() {
+        show.alternative_title = show.title.clone();
+    }
+    show.link_to_show = link.to_owned();
+    Ok(show.into())
+}
+
+impl SiteParser for Generic {
+    fn name(&self) -> &'static str {
+        "Web page"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        super::host(url).is_some()
+    }
+
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow> {
+        let headers = http::language_headers(config.language(self.name()));
+        let html = HTML::from_string(http::get(url, &headers)?, Some(url.to_owned()))?;
+        parse(&html, url)
+    }
+}
@@ -0,0 +1,248 @@
+use super::{generic, ImportedShow, SearchResult, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{ShowRecord, ShowType},
+    parsers,
+};
+use anyhow::Result;
+use regex::Regex;
+use serde_json::Value;
+
+pub struct Imdb;
+
+/// The suggestions of the search box, the only search IMDb has without scraping.
+const SUGGESTION_API: &str = "https://v3.sg.media-imdb.com/suggestion/x";
+
+/// Title types of films, the others are series or episodes of them.
+const FILM_TYPES: [&str; 6] = ["movie", "tvMovie", "short", "tvShort", "video", "tvSpecial"];
+
+fn script(page: &str, attribute: &str) -> Result<Vec<Value>> {
+    let pattern = format!(r#"(?s)<script[^>]*{}[^>]*>(.*?)</script>"#, attribute);
+    Ok(Regex::new(&pattern)?
+        .captures_iter(page)
+        .filter_map(|c| serde_json::from_str(&c[1]).ok())
+        .collect())
+}
+
+fn show_type(is_film: bool, genres: &[String], countries: &[String]) -> ShowType {
+    if is_film {
+        ShowType::Film
+    } else if !genres.iter().any(|g| g == "Animation") {
+        ShowType::Serial
+    } else if countries.iter().any(|c| c == "JP") {
+        ShowType::Anime
+    } else {
+        ShowType::Cartoon
+    }
+}
+
+/// The data the page is rendered from, in the language IMDb picked for the request.
+fn next_data(page: &str) -> Result<Option<ImportedShow>> {
+    let Some(data) = script(page, r#"id="__NEXT_DATA__""#)?.pop() else {
+        return Ok(None);
+    };
+    let Some(title) = data.pointer("/props/pageProps/aboveTheFoldData") else {
+        return Ok(None);
+    };
+    let columns = &data["props"]["pageProps"]["mainColumnData"];
+    let texts = |value: &Value, key: &str| match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(|item| item[key].as_str().map(String::from))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let genres = texts(&title["genres"]["genres"], "text");
+    let mut countries = texts(&title["countriesOfOrigin"]["countries"], "id");
+    if countries.is_empty() {
+        countries = texts(&columns["countriesOfOrigin"]["countries"], "id");
+    }
+    let title_type = title["titleType"]["id"].as_str().unwrap_or_default();
+    let release_date = match (
+        title["releaseYear"]["year"].as_i64(),
+        title["releaseYear"]["endYear"].as_i64(),
+    ) {
+        (Some(year), Some(end)) if end != year => format!("{}–{}", year, end),
+        // Written like the page does for series still running.
+        (Some(year), None) if title["titleType"]["isSeries"] == true => format!("{}– ", year),
+        (Some(year), _) => year.to_string(),
+        (None, _) => String::new(),
+    };
+    let seasons = match &columns["episodes"]["seasons"] {
+        Value::Array(seasons) if !seasons.is_empty() => Some(seasons.len() as i32),
+        _ => None,
+    };
+
+    let show = ShowRecord {
+        title: generic::text(&title["titleText"]["text"]).unwrap_or_default(),
+        alternative_title: generic::text(&title["originalTitleText"]["text"]).unwrap_or_default(),
+        release_date,
+        about: generic::text(&title["plot"]["plotText"]["plainText"]).unwrap_or_default(),
+        link_to_picture: generic::text(&title["primaryImage"]["url"]).unwrap_or_default(),
+        show_type: show_type(FILM_TYPES.contains(&title_type), &genres, &countries),
+        ..Default::default()
+    };
+    Ok(Some(ImportedShow {
+        show,
+        genres,
+        runtime: title["runtime"]["seconds"]
+            .as_u64()
+            .map(|s| (s / 60) as u32),
+        rating: title["ratingsSummary"]["aggregateRating"]
+            .as_f64()
+            .map(|r| r as f32),
+        seasons,
+    }))
+}
+
+/// Minutes of an ISO 8601 duration like `PT1H47M`.
+fn minutes(duration: &str) -> Option<u32> {
+    let captures = Regex::new(r"^PT(?:(\d+)H)?(?:(\d+)M)?")
+        .ok()?
+        .captures(duration)?;
+    let part = |i| captures.get(i).map_or(Ok(0), |m| m.as_str().parse::<u32>());
+    let minutes = part(1).ok()? * 60 + part(2).ok()?;
+    (minutes > 0).then_some(minutes)
+}
+
+/// The schema.org data of the page, where the name is the original title.
+fn json_ld(page: &str) -> Result<Option<ImportedShow>> {
+    let item = script(page, r#"type="application/ld\+json""#)?
+        .into_iter()
+        .find(|item| matches!(item["@type"].as_str(), Some("TVSeries" | "Movie")));
+    let Some(item) = item else {
+        return Ok(None);
+    };
+
+    let genres = match &item["genre"] {
+        Value::Array(genres) => genres.iter().filter_map(generic::text).collect(),
+        genre => generic::text(genre).into_iter().collect::<Vec<_>>(),
+    };
+    let name = generic::text(&item["name"]).unwrap_or_default();
+    let show = ShowRecord {
+        title: generic::text(&item["alternateName"]).unwrap_or(name.clone()),
+        alternative_title: name,
+        release_date: generic::text(&item["datePublished"])
+            .and_then(|date| date.split('-').next().map(String::from))
+            .unwrap_or_default(),
+        about: generic::text(&item["description"]).unwrap_or_default(),
+        link_to_picture: generic::image(&item["image"]).unwrap_or_default(),
+        show_type: show_type(item["@type"] == "Movie", &genres, &[]),
+        ..Default::default()
+    };
+    Ok(Some(ImportedShow {
+        show,
+        genres,
+        runtime: item["duration"].as_str().and_then(minutes),
+        rating: item["aggregateRating"]["ratingValue"]
+            .as_f64()
+            .map(|r| r as f32),
+        seasons: None,
+    }))
+}
+
+/// Reads the markup of the title page, which breaks whenever IMDb renames its classes.
+fn markup(page: &str, link: &str) -> Result<ShowRecord> {
+    parsers::scrab(
+        page,
+        link,
+        r#"hero__primary-text">([^<]+)"#,
+        r#"Original title: ([^<]+)"#,
+        r#"releaseinfo\?ref_=tt_ov_rdat">([^<]+)"#,
+        r#"bruFve">([^<]+)"#,
+        r#"class="ipc-image" loading="eager" src="([^"]+)"#,
+    )
+}
+
+fn fill(field: &mut String, value: &str) {
+    if field.is_empty() {
+        *field = value.to_owned();
+    }
+}
+
+/// Reads a title page, from the data it is rendered from, then its schema.org data, then its
+/// markup, each filling what the ones before lack. Fields found nowhere are left empty.
+pub fn parse(page: &str, link: &str) -> Result<ImportedShow> {
+    let structured = [next_data(page)?, json_ld(page)?];
+    let mut sources = structured.into_iter().flatten();
+    let mut imported = sources.next().unwrap_or_default();
+    for other in sources.chain([markup(page, link)?.into()]) {
+        let show = &mut imported.show;
+        fill(&mut show.title, &other.show.title);
+        fill(&mut show.alternative_title, &other.show.alternative_title);
+        fill(&mut show.release_date, &other.show.release_date);
+        fill(&mut show.about, &other.show.about);
+        fill(&mut show.link_to_picture, &other.show.link_to_picture);
+        if imported.genres.is_empty() {
+            imported.genres = other.genres;
+        }
+        imported.runtime = imported.runtime.or(other.runtime);
+        imported.rating = imported.rating.or(other.rating);
+        imported.seasons = imported.seasons.or(other.seasons);
+    }
+
+    let show = &mut imported.show;
+    if show.alternative_title.is_empty() {
+        show.alternative_title = show.title.clone();
+    }
+    show.link_to_show = link.to_owned();
+    Ok(imported)
+}
+
+/// Reads the suggestions for a query, keeping the titles and leaving out people.
+pub fn parse_search(content: &str) -> Result<Vec<SearchResult>> {
+    let response: Value = serde_json::from_str(content)?;
+    let Value::Array(suggestions) = &response["d"] else {
+        return Ok(Vec::new());
+    };
+    Ok(suggestions
+        .iter()
+        .filter_map(|suggestion| {
+            let id = suggestion["id"]
+                .as_str()
+                .filter(|id| id.starts_with("tt"))?;
+            let title_type = suggestion["qid"].as_str().unwrap_or_default();
+            if title_type == "videoGame" {
+                return None;
+            }
+            Some(SearchResult {
+                title: generic::text(&suggestion["l"])?,
+                year: generic::text(&suggestion["yr"])
+                    .or_else(|| suggestion["y"].as_i64().map(|year| year.to_string()))
+                    .unwrap_or_default(),
+                show_type: show_type(FILM_TYPES.contains(&title_type), &[], &[]),
+                link: format!("https://www.imdb.com/title/{}/", id),
+                picture: generic::text(&suggestion["i"]["imageUrl"]).unwrap_or_default(),
+                ..Default::default()
+            })
+        })
+        .collect())
+}
+
+/// Reads a title page, asking for it in the language. IMDb picks one by the address of the
+/// request when no language is asked for.
+pub fn fetch(url: &str, language: Option<&str>) -> Result<ImportedShow> {
+    parse(&http::get(url, &http::language_headers(language))?, url)
+}
+
+impl SiteParser for Imdb {
+    fn name(&self) -> &'static str {
+        "IMDb"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        super::is_on_domain(url, "imdb.com")
+    }
+
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow> {
+        fetch(url, config.language(self.name()))
+    }
+
+    fn search(&self, title: &str, config: &Config) -> Result<Vec<SearchResult>> {
+        let url = format!("{}/{}.json", SUGGESTION_API, http::encode(title));
+        let headers = http::language_headers(config.language(self.name()));
+        parse_search(&http::get(&url, &headers)?)
+    }
+}
@@ -0,0 +1,285 @@
+pub mod anilist;
+pub mod generic;
+pub mod imdb;
+pub mod myanimelist;
+pub mod tmdb;
+pub mod tvmaze;
+
+use crate::{
+    config::Config,
+    http,
+    model::{ShowRecord, ShowType},
+};
+use anyhow::Result;
+use std::fmt;
+
+/// A show read from the page of a site, with what the site tells about it that the watchlist
+/// does not keep.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportedShow {
+    pub show: ShowRecord,
+    pub genres: Vec<String>,
+    /// Minutes of the film, or of an episode.
+    pub runtime: Option<u32>,
+    /// The rating by the users of the site, out of 10.
+    pub rating: Option<f32>,
+    pub seasons: Option<i32>,
+}
+
+impl ImportedShow {
+    /// What the page tells besides the show's fields, such as "Drama, Fantasy · 24 min · 8.8/10 · 2 seasons".
+    pub fn details(&self) -> String {
+        let mut details = Vec::new();
+        if !self.genres.is_empty() {
+            details.push(self.genres.join(", "));
+        }
+        if let Some(runtime) = self.runtime {
+            details.push(format!("{} min", runtime));
+        }
+        if let Some(rating) = self.rating {
+            details.push(format!("{:.1}/10", rating));
+        }
+        match self.seasons {
+            Some(1) => details.push("1 season".to_owned()),
+            Some(seasons) => details.push(format!("{} seasons", seasons)),
+            None => {}
+        }
+        details.join(" · ")
+    }
+}
+
+impl From<ShowRecord> for ImportedShow {
+    fn from(show: ShowRecord) -> Self {
+        ImportedShow {
+            show,
+            ..Default::default()
+        }
+    }
+}
+
+/// A site shows can be imported from. Each site is a module of its own, added to `SITES`.
+pub trait SiteParser: Sync {
+    fn name(&self) -> &'static str;
+    /// Whether the link is to a page this site can import.
+    fn matches(&self, url: &str) -> bool;
+    /// Imports the show, in the language `config` sets for the site.
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow>;
+    /// Shows with a title like this one, best matches first. Sites without a search find none.
+    fn search(&self, _title: &str, _config: &Config) -> Result<Vec<SearchResult>> {
+        Ok(Vec::new())
+    }
+}
+
+/// A show found by searching a site, imported from its link once picked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchResult {
+    pub title: String,
+    /// The year of the release, or the years the show ran.
+    pub year: String,
+    pub show_type: ShowType,
+    pub link: String,
+    pub picture: String,
+    /// The name of the site, set by [`search`].
+    pub site: &'static str,
+}
+
+/// How many results of each site a search keeps.
+const SEARCH_LIMIT: usize = 5;
+
+/// Sites in the order they are tried, the first one matching a link imports it.
+static SITES: &[&dyn SiteParser] = &[
+    &imdb::Imdb,
+    &tmdb::Tmdb,
+    &tvmaze::Tvmaze,
+    &anilist::AniList,
+    &myanimelist::MyAnimeList,
+    &generic::Generic,
+];
+
+/// The host of a link in lower case, without the scheme, port or path.
+pub fn host(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    match host.is_empty() {
+        true => None,
+        false => Some(host.to_lowercase()),
+    }
+}
+
+/// The segments of the path of a link, without the query and fragment.
+pub fn path_segments(url: &str) -> Vec<&str> {
+    let path = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default();
+    path.split('/')
+        .skip(1)
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+/// Whether the link is to the domain, or one of its subdomains.
+pub fn is_on_domain(url: &str, domain: &str) -> bool {
+    host(url).is_some_and(|host| {
+        host == domain
+            || host
+                .strip_suffix(domain)
+                .is_some_and(|sub| sub.ends_with('.'))
+    })
+}
+
+/// The title of an anime in the language, and the one it is also known by. Without a title in
+/// the language, the romaji one comes first, followed by the English one.
+pub fn anime_titles(
+    romaji: &str,
+    english: Option<&str>,
+    japanese: Option<&str>,
+    language: Option<&str>,
+) -> (String, String) {
+    let code = language.and_then(|l| l.split(['-', '_']).next());
+    let localized = match code {
+        Some(code) if code.eq_ignore_ascii_case("en") => english,
+        Some(code) if code.eq_ignore_ascii_case("ja") => japanese,
+        _ => None,
+    };
+    match localized.filter(|title| !title.is_empty() && *title != romaji) {
+        Some(title) => (title.to_owned(), romaji.to_owned()),
+        None => (romaji.to_owned(), english.unwrap_or(romaji).to_owned()),
+    }
+}
+
+pub fn find(link: &str) -> Option<&'static dyn SiteParser> {
+    SITES.iter().copied().find(|site| site.matches(link))
+}
+
+pub fn check_link_is_importable(link: &str) -> bool {
+    find(link).is_some()
+}
+
+/// Why a link could not be imported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// The site could not be reached.
+    Network(String),
+    Status {
+        url: String,
+        status: u32,
+    },
+    Unsupported(String),
+    /// The page was read, but without a title to add the show by.
+    MissingFields {
+        found: Vec<&'static str>,
+        missing: Vec<&'static str>,
+    },
+    Parse(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Network(e) => write!(f, "Could not connect: {}", e),
+            ImportError::Status { url, status } => {
+                write!(f, "{} answered with status {}", url, status)
+            }
+            ImportError::Unsupported(link) => write!(f, "Importing from {} is not supported", link),
+            ImportError::MissingFields { found, missing } if found.is_empty() => {
+                write!(f, "Found no {} on the page", missing.join(", "))
+            }
+            ImportError::MissingFields { found, missing } => write!(
+                f,
+                "Found no {} on the page, only {}",
+                missing.join(", "),
+                found.join(", ")
+            ),
+            ImportError::Parse(e) => write!(f, "Could not read the page: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<anyhow::Error> for ImportError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(e) = e.downcast_ref::<http::StatusError>() {
+            ImportError::Status {
+                url: e.url.clone(),
+                status: e.status,
+            }
+        } else if let Some(e) = e.downcast_ref::<curl::Error>() {
+            ImportError::Network(e.to_string())
+        } else {
+            ImportError::Parse(format!("{:#}", e))
+        }
+    }
+}
+
+/// The fields of an imported show that were found on the page, and the ones that were not.
+pub fn fields(show: &ShowRecord) -> (Vec<&'static str>, Vec<&'static str>) {
+    let fields = [
+        ("title", !show.title.is_empty()),
+        ("release date", !show.release_date.is_empty()),
+        ("description", !show.about.is_empty()),
+        ("picture", !show.link_to_picture.is_empty()),
+        ("episodes count", show.episodes_count > 0),
+        ("schedule", !show.schedule.release_time.is_empty()),
+    ];
+    let names = |found: bool| {
+        fields
+            .iter()
+            .filter(|field| field.1 == found)
+            .map(|field| field.0)
+            .collect()
+    };
+    (names(true), names(false))
+}
+
+pub fn fetch(link: &str, config: &Config) -> Result<ImportedShow, ImportError> {
+    let Some(site) = find(link) else {
+        return Err(ImportError::Unsupported(link.to_owned()));
+    };
+    let imported = site.fetch(link, config)?;
+    if imported.show.title.is_empty() {
+        let (found, missing) = fields(&imported.show);
+        return Err(ImportError::MissingFields { found, missing });
+    }
+    Ok(imported)
+}
+
+/// Searches every site at once, keeping the first results of each in the order of `SITES`. The
+/// sites that failed are returned with their errors.
+pub fn search(
+    title: &str,
+    config: &Config,
+) -> (Vec<SearchResult>, Vec<(&'static str, ImportError)>) {
+    let title = title.trim();
+    let answers = std::thread::scope(|scope| {
+        let searches = SITES
+            .iter()
+            .map(|site| scope.spawn(move || (site.name(), site.search(title, config))))
+            .collect::<Vec<_>>();
+        searches
+            .into_iter()
+            .filter_map(|search| search.join().ok())
+            .collect::<Vec<_>>()
+    });
+
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    for (site, answer) in answers {
+        match answer {
+            Ok(found) => results.extend(
+                found
+                    .into_iter()
+                    .take(SEARCH_LIMIT)
+                    .map(|result| SearchResult { site, ..result }),
+            ),
+            Err(e) => errors.push((site, e.into())),
+        }
+    }
+    (results, errors)
+}
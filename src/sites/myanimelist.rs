@@ -0,0 +1,226 @@
+use super::{ImportedShow, SearchResult, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{Schedule, ShowRecord, ShowType},
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveTime, TimeZone, Weekday};
+use serde::Deserialize;
+
+/// Jikan serves the data of MyAnimeList pages without the API key MyAnimeList asks for.
+const API: &str = "https://api.jikan.moe/v4";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Picture {
+    image_url: Option<String>,
+    large_image_url: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Images {
+    jpg: Picture,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Aired {
+    from: Option<String>,
+    to: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Broadcast {
+    /// Like "Saturdays".
+    day: Option<String>,
+    time: Option<String>,
+    timezone: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Anime {
+    url: String,
+    title: String,
+    /// Like `TV` or `Movie`.
+    #[serde(rename = "type")]
+    anime_type: Option<String>,
+    title_english: Option<String>,
+    title_japanese: Option<String>,
+    synopsis: Option<String>,
+    episodes: Option<i32>,
+    images: Images,
+    aired: Aired,
+    broadcast: Broadcast,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Response {
+    data: Option<Anime>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchResponse {
+    data: Vec<Anime>,
+}
+
+pub struct MyAnimeList;
+
+/// The id of an anime page, like `https://myanimelist.net/anime/52991/Sousou_no_Frieren`.
+pub fn anime_id(url: &str) -> Option<u64> {
+    if !super::is_on_domain(url, "myanimelist.net") {
+        return None;
+    }
+    match super::path_segments(url)[..] {
+        ["anime", id, ..] => id.parse().ok(),
+        _ => None,
+    }
+}
+
+fn year(date: &str) -> &str {
+    date.split('-').next().unwrap_or_default()
+}
+
+/// Films are told apart from the other types, which are all anime.
+fn show_type(anime: &Anime) -> ShowType {
+    match anime.anime_type.as_deref() {
+        Some("Movie") => ShowType::Film,
+        _ => ShowType::Anime,
+    }
+}
+
+/// The first broadcast, from the day the anime started airing and the time it is broadcast at
+/// every week. Only Japanese time is known, which has no daylight saving time.
+fn schedule(anime: &Anime) -> Option<Schedule> {
+    let broadcast = &anime.broadcast;
+    if broadcast.timezone.as_deref() != Some("Asia/Tokyo") {
+        return None;
+    }
+    let weekday = broadcast
+        .day
+        .as_deref()?
+        .trim_end_matches('s')
+        .parse::<Weekday>()
+        .ok()?;
+    let time = NaiveTime::parse_from_str(broadcast.time.as_deref()?, "%H:%M").ok()?;
+    let from = DateTime::parse_from_rfc3339(anime.aired.from.as_deref()?).ok()?;
+
+    // The start date is given as midnight UTC of the day in Japan.
+    let mut date = from.date_naive();
+    while date.weekday() != weekday {
+        date = date.succ_opt()?;
+    }
+    let tokyo = FixedOffset::east_opt(9 * 3600)?;
+    let first = tokyo
+        .from_local_datetime(&date.and_time(time))
+        .single()?
+        .with_timezone(&Local);
+
+    let mut episodes_per_weekday = [0; 7];
+    episodes_per_weekday[first.weekday().num_days_from_monday() as usize] = 1;
+    Some(Schedule {
+        release_time: first.format("%Y-%m-%d %H:%M").to_string(),
+        episodes_per_weekday,
+    })
+}
+
+/// Reads a response of the Jikan anime API, titled in the language when MyAnimeList has a title
+/// in it.
+pub fn parse(content: &str, link: &str, language: Option<&str>) -> Result<ImportedShow> {
+    let response: Response =
+        serde_json::from_str(content).context("Failed to parse the MyAnimeList response")?;
+    let Some(anime) = response.data.filter(|a| !a.title.is_empty()) else {
+        bail!("MyAnimeList has no anime for {}", link);
+    };
+
+    let start = anime.aired.from.as_deref().map(year).unwrap_or_default();
+    let release_date = match anime.aired.to.as_deref().map(year) {
+        Some(end) if end != start => format!("{}–{}", start, end),
+        _ => start.to_owned(),
+    };
+    // Synopses end with a credit like "[Written by MAL Rewrite]".
+    let about = anime.synopsis.as_deref().unwrap_or_default();
+    let about = match about.rfind("[Written by") {
+        Some(credit) => &about[..credit],
+        None => about,
+    };
+
+    let (title, alternative_title) = super::anime_titles(
+        &anime.title,
+        anime.title_english.as_deref(),
+        anime.title_japanese.as_deref(),
+        language,
+    );
+    Ok(ShowRecord {
+        title,
+        alternative_title,
+        release_date,
+        about: about.trim().to_owned(),
+        link_to_show: link.to_owned(),
+        link_to_picture: anime
+            .images
+            .jpg
+            .large_image_url
+            .clone()
+            .or(anime.images.jpg.image_url.clone())
+            .unwrap_or_default(),
+        show_type: show_type(&anime),
+        episodes_count: anime.episodes.unwrap_or_default(),
+        schedule: schedule(&anime).unwrap_or_default(),
+        ..Default::default()
+    }
+    .into())
+}
+
+/// Reads a response of the Jikan anime search API.
+pub fn parse_search(content: &str) -> Result<Vec<SearchResult>> {
+    let response: SearchResponse =
+        serde_json::from_str(content).context("Failed to parse the MyAnimeList search response")?;
+    Ok(response
+        .data
+        .into_iter()
+        .filter(|anime| !anime.title.is_empty())
+        .map(|anime| SearchResult {
+            year: anime
+                .aired
+                .from
+                .as_deref()
+                .map(year)
+                .unwrap_or_default()
+                .to_owned(),
+            show_type: show_type(&anime),
+            picture: anime.images.jpg.image_url.unwrap_or_default(),
+            title: anime.title,
+            link: anime.url,
+            ..Default::default()
+        })
+        .collect())
+}
+
+impl SiteParser for MyAnimeList {
+    fn name(&self) -> &'static str {
+        "MyAnimeList"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        anime_id(url).is_some()
+    }
+
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow> {
+        let Some(id) = anime_id(url) else {
+            bail!("{} is not a MyAnimeList anime", url);
+        };
+        let content = http::get(&format!("{}/anime/{}", API, id), &[])?;
+        parse(&content, url, config.language(self.name()))
+    }
+
+    fn search(&self, title: &str, _config: &Config) -> Result<Vec<SearchResult>> {
+        let url = format!("{}/anime?q={}&limit=10", API, http::encode(title));
+        parse_search(&http::get(&url, &[])?)
+    }
+}
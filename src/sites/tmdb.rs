@@ -0,0 +1,297 @@
+use super::{ImportedShow, SearchResult, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{Schedule, ShowRecord, ShowType},
+};
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+
+const API: &str = "https://api.themoviedb.org/3";
+const POSTER_URL: &str = "https://image.tmdb.org/t/p/w500";
+const THUMBNAIL_URL: &str = "https://image.tmdb.org/t/p/w154";
+const ANIMATION_GENRE: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Media {
+    Tv,
+    Movie,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Genre {
+    id: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Season {
+    season_number: i32,
+    episode_count: i32,
+    air_date: Option<String>,
+}
+
+/// The fields of a movie or TV show, which TMDB names differently.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Details {
+    #[serde(alias = "name")]
+    title: String,
+    #[serde(alias = "original_name")]
+    original_title: String,
+    overview: String,
+    #[serde(alias = "first_air_date")]
+    release_date: String,
+    last_air_date: Option<String>,
+    status: String,
+    poster_path: Option<String>,
+    genres: Vec<Genre>,
+    original_language: String,
+    origin_country: Vec<String>,
+    number_of_episodes: i32,
+    number_of_seasons: i32,
+    seasons: Vec<Season>,
+}
+
+/// A movie, TV show or person found by the multi search.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Found {
+    id: u64,
+    media_type: String,
+    #[serde(alias = "name")]
+    title: String,
+    #[serde(alias = "first_air_date")]
+    release_date: String,
+    poster_path: Option<String>,
+    genre_ids: Vec<u32>,
+    original_language: String,
+    origin_country: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SearchResponse {
+    results: Vec<Found>,
+}
+
+pub struct Tmdb;
+
+/// The kind and id of a TMDB page, like `https://www.themoviedb.org/tv/71738-the-orville`.
+pub fn media(url: &str) -> Option<(Media, u64)> {
+    if !super::is_on_domain(url, "themoviedb.org") {
+        return None;
+    }
+    let (media, slug) = match super::path_segments(url)[..] {
+        ["tv", slug, ..] => (Media::Tv, slug),
+        ["movie", slug, ..] => (Media::Movie, slug),
+        _ => return None,
+    };
+    let id = slug.split('-').next()?.parse().ok()?;
+    Some((media, id))
+}
+
+fn year(date: &str) -> &str {
+    date.split('-').next().unwrap_or_default()
+}
+
+fn show_type(media: Media, details: &Details) -> ShowType {
+    if media == Media::Movie {
+        return ShowType::Film;
+    }
+    if !details.genres.iter().any(|g| g.id == ANIMATION_GENRE) {
+        return ShowType::Serial;
+    }
+    match details.original_language == "ja" || details.origin_country.iter().any(|c| c == "JP") {
+        true => ShowType::Anime,
+        false => ShowType::Cartoon,
+    }
+}
+
+fn release_date(media: Media, details: &Details) -> String {
+    let first = year(&details.release_date);
+    let last = details.last_air_date.as_deref().map(year);
+    match last {
+        Some(last)
+            if media == Media::Tv
+                && last != first
+                && matches!(details.status.as_str(), "Ended" | "Canceled") =>
+        {
+            format!("{}–{}", first, last)
+        }
+        _ => first.to_owned(),
+    }
+}
+
+/// TMDB only knows the dates of seasons, so the schedule is one episode a week on the weekday
+/// the latest season started.
+fn schedule(season: &Season) -> Schedule {
+    let start = season
+        .air_date
+        .as_deref()
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+    match start {
+        Some(start) => {
+            let mut episodes_per_weekday = [0; 7];
+            episodes_per_weekday[start.weekday().num_days_from_monday() as usize] = 1;
+            Schedule {
+                release_time: format!("{} 00:00", start.format("%Y-%m-%d")),
+                episodes_per_weekday,
+            }
+        }
+        None => Schedule::default(),
+    }
+}
+
+/// Reads a response of the movie or TV details API.
+pub fn parse(media: Media, content: &str, link: &str) -> Result<ImportedShow> {
+    let details: Details =
+        serde_json::from_str(content).context("Failed to parse the TMDB response")?;
+    if details.title.is_empty() {
+        bail!("TMDB has no title for {}", link);
+    }
+
+    let mut show = ShowRecord {
+        alternative_title: match details.original_title.is_empty() {
+            true => details.title.clone(),
+            false => details.original_title.clone(),
+        },
+        release_date: release_date(media, &details),
+        about: details.overview.clone(),
+        link_to_show: link.to_owned(),
+        link_to_picture: details
+            .poster_path
+            .as_deref()
+            .map(|path| format!("{}{}", POSTER_URL, path))
+            .unwrap_or_default(),
+        show_type: show_type(media, &details),
+        ..Default::default()
+    };
+    let mut seasons = None;
+    match media {
+        Media::Movie => show.episodes_count = 1,
+        Media::Tv => {
+            // Counts and dates of the season being aired, specials are season 0.
+            let latest = details
+                .seasons
+                .iter()
+                .filter(|s| s.season_number > 0)
+                .max_by_key(|s| s.season_number);
+            match latest {
+                Some(season) => {
+                    show.episodes_count = season.episode_count;
+                    show.schedule = schedule(season);
+                }
+                None => show.episodes_count = details.number_of_episodes,
+            }
+            seasons = match details.number_of_seasons {
+                0 => latest.map(|s| s.season_number),
+                count => Some(count),
+            };
+        }
+    }
+    show.title = details.title;
+    Ok(ImportedShow {
+        seasons,
+        ..show.into()
+    })
+}
+
+/// Reads a response of the multi search API, skipping the people it finds.
+pub fn parse_search(content: &str) -> Result<Vec<SearchResult>> {
+    let response: SearchResponse =
+        serde_json::from_str(content).context("Failed to parse the TMDB search response")?;
+    let mut results = Vec::new();
+    for found in response.results {
+        let (media, kind) = match found.media_type.as_str() {
+            "tv" => (Media::Tv, "tv"),
+            "movie" => (Media::Movie, "movie"),
+            _ => continue,
+        };
+        let details = Details {
+            genres: found.genre_ids.iter().map(|&id| Genre { id }).collect(),
+            original_language: found.original_language,
+            origin_country: found.origin_country,
+            ..Default::default()
+        };
+        results.push(SearchResult {
+            year: year(&found.release_date).to_owned(),
+            show_type: show_type(media, &details),
+            link: format!("https://www.themoviedb.org/{}/{}", kind, found.id),
+            picture: found
+                .poster_path
+                .map(|path| format!("{}{}", THUMBNAIL_URL, path))
+                .unwrap_or_default(),
+            title: found.title,
+            ..Default::default()
+        });
+    }
+    Ok(results)
+}
+
+fn api_key(config: &Config) -> Option<&str> {
+    config.tmdb_api_key.as_deref().filter(|k| !k.is_empty())
+}
+
+/// The parameter asking for titles and overviews in the language, TMDB answers in English
+/// without one.
+fn language_param(language: Option<&str>) -> String {
+    match language {
+        Some(language) => format!("&language={}", http::encode(language)),
+        None => String::new(),
+    }
+}
+
+impl SiteParser for Tmdb {
+    fn name(&self) -> &'static str {
+        "TMDB"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        media(url).is_some()
+    }
+
+    fn fetch(&self, url: &str, config: &Config) -> Result<ImportedShow> {
+        let Some((media, id)) = media(url) else {
+            bail!("{} is not a TMDB movie or TV show", url);
+        };
+        let Some(api_key) = api_key(config) else {
+            bail!(
+                "Set tmdb_api_key in {} to import from TMDB",
+                Config::path().unwrap_or_default().display()
+            );
+        };
+
+        let kind = match media {
+            Media::Tv => "tv",
+            Media::Movie => "movie",
+        };
+        let details_url = format!(
+            "{}/{}/{}?api_key={}{}",
+            API,
+            kind,
+            id,
+            api_key,
+            language_param(config.language(self.name()))
+        );
+        let content = http::get(&details_url, &[])?;
+        parse(media, &content, url)
+    }
+
+    /// Searches only with an API key, without one TMDB is left out.
+    fn search(&self, title: &str, config: &Config) -> Result<Vec<SearchResult>> {
+        let Some(api_key) = api_key(config) else {
+            return Ok(Vec::new());
+        };
+        let url = format!(
+            "{}/search/multi?api_key={}&query={}{}",
+            API,
+            api_key,
+            http::encode(title),
+            language_param(config.language(self.name()))
+        );
+        parse_search(&http::get(&url, &[])?)
+    }
+}
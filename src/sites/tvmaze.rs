@@ -0,0 +1,310 @@
+use super::{ImportedShow, SearchResult, SiteParser};
+use crate::{
+    config::Config,
+    http,
+    model::{Schedule, ShowRecord, ShowType},
+    parsers,
+};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+const API: &str = "https://api.tvmaze.com";
+const RELEASE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TvmazeSchedule {
+    time: String,
+    days: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Image {
+    medium: Option<String>,
+    original: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Episode {
+    season: i32,
+    /// Not set for specials.
+    number: Option<i32>,
+    airdate: String,
+    airstamp: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Country {
+    timezone: String,
+}
+
+/// A TV network or web channel. Worldwide web channels have no country.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Network {
+    country: Option<Country>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Embedded {
+    episodes: Vec<Episode>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Show {
+    name: String,
+    url: String,
+    #[serde(rename = "type")]
+    show_type: String,
+    language: Option<String>,
+    genres: Vec<String>,
+    premiered: Option<String>,
+    ended: Option<String>,
+    schedule: TvmazeSchedule,
+    network: Option<Network>,
+    #[serde(rename = "webChannel")]
+    web_channel: Option<Network>,
+    image: Option<Image>,
+    summary: Option<String>,
+    #[serde(rename = "_embedded")]
+    embedded: Embedded,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Found {
+    show: Show,
+}
+
+pub struct Tvmaze;
+
+/// The id of a show page, like `https://www.tvmaze.com/shows/70145/kaiju-no-8`.
+pub fn show_id(url: &str) -> Option<u64> {
+    if !super::is_on_domain(url, "tvmaze.com") {
+        return None;
+    }
+    match super::path_segments(url)[..] {
+        ["shows", id, ..] => id.parse().ok(),
+        _ => None,
+    }
+}
+
+fn year(date: &str) -> &str {
+    date.split('-').next().unwrap_or_default()
+}
+
+/// Air times are given in UTC, the schedule is kept in local time.
+fn local_time(airstamp: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(airstamp)
+        .ok()
+        .map(|t| t.with_timezone(&Local).naive_local())
+}
+
+fn show_type(show: &Show) -> ShowType {
+    if show.show_type != "Animation" {
+        return ShowType::Serial;
+    }
+    match show.language.as_deref() == Some("Japanese") || show.genres.iter().any(|g| g == "Anime") {
+        true => ShowType::Anime,
+        false => ShowType::Cartoon,
+    }
+}
+
+/// The most common number of episodes released on one day of each weekday. Only the days of
+/// the network's schedule count, so a premiere on another day does not become a weekly release.
+fn episodes_per_weekday(show: &Show, episodes: &[(&Episode, NaiveDateTime)]) -> [u32; 7] {
+    let scheduled = |episode: &Episode| {
+        let airdate = NaiveDate::parse_from_str(&episode.airdate, "%Y-%m-%d");
+        show.schedule.days.is_empty()
+            || airdate.is_ok_and(|date| {
+                show.schedule
+                    .days
+                    .iter()
+                    .any(|day| day.parse::<Weekday>() == Ok(date.weekday()))
+            })
+    };
+
+    let mut per_date: HashMap<NaiveDate, u32> = HashMap::new();
+    for (_, time) in episodes.iter().filter(|(e, _)| scheduled(e)) {
+        *per_date.entry(time.date()).or_default() += 1;
+    }
+    let mut counts: [HashMap<u32, u32>; 7] = Default::default();
+    for (date, episodes) in per_date {
+        *counts[date.weekday().num_days_from_monday() as usize]
+            .entry(episodes)
+            .or_default() += 1;
+    }
+    counts.map(|counts| {
+        counts
+            .into_iter()
+            .max_by_key(|&(episodes, days)| (days, episodes))
+            .map_or(0, |(episodes, _)| episodes)
+    })
+}
+
+fn timezone(show: &Show) -> Option<Tz> {
+    let network = show.network.as_ref().or(show.web_channel.as_ref())?;
+    network.country.as_ref()?.timezone.parse().ok()
+}
+
+/// Without episode air times, one episode on every day of the schedule from the premiere of the
+/// season. The schedule is in the time zone of the network, so it is left empty when that or the
+/// time of day is unknown.
+fn announced_schedule(show: &Show, season_premiere: Option<&str>) -> Schedule {
+    let premiere = season_premiere.and_then(|date| {
+        let time = format!("{} {}", date, show.schedule.time);
+        NaiveDateTime::parse_from_str(&time, RELEASE_TIME_FORMAT).ok()
+    });
+    let local = premiere
+        .zip(timezone(show))
+        .and_then(|(premiere, timezone)| {
+            let aired = timezone.from_local_datetime(&premiere).earliest()?;
+            Some((premiere, aired.with_timezone(&Local).naive_local()))
+        });
+    let Some((premiere, local)) = local else {
+        return Schedule::default();
+    };
+
+    // The days move along with the premiere when the time zones are a day apart.
+    let shift = (local.date() - premiere.date()).num_days();
+    let mut episodes_per_weekday = [0; 7];
+    for day in &show.schedule.days {
+        if let Ok(weekday) = day.parse::<Weekday>() {
+            let index = (weekday.num_days_from_monday() as i64 + shift).rem_euclid(7);
+            episodes_per_weekday[index as usize] = 1;
+        }
+    }
+    Schedule {
+        release_time: local.format(RELEASE_TIME_FORMAT).to_string(),
+        episodes_per_weekday,
+    }
+}
+
+/// Reads a response of the show API with its episodes embedded. The episodes count and
+/// schedule are those of the latest season.
+pub fn parse(content: &str, link: &str) -> Result<ImportedShow> {
+    let show: Show =
+        serde_json::from_str(content).context("Failed to parse the TVmaze response")?;
+    if show.name.is_empty() {
+        bail!("TVmaze has no title for {}", link);
+    }
+
+    let season = show
+        .embedded
+        .episodes
+        .iter()
+        .map(|e| e.season)
+        .max()
+        .unwrap_or(1);
+    let mut episodes = show
+        .embedded
+        .episodes
+        .iter()
+        .filter(|e| e.season == season && e.number.is_some())
+        .collect::<Vec<_>>();
+    episodes.sort_by_key(|e| e.number);
+    let aired = episodes
+        .iter()
+        .filter_map(|e| Some((*e, local_time(e.airstamp.as_deref()?)?)))
+        .collect::<Vec<_>>();
+
+    let schedule = match aired.iter().map(|(_, time)| time).min() {
+        Some(first) => Schedule {
+            release_time: first.format(RELEASE_TIME_FORMAT).to_string(),
+            episodes_per_weekday: episodes_per_weekday(&show, &aired),
+        },
+        None => {
+            // The first season starts with the show, later ones with their first episode.
+            let airdates = episodes.iter().map(|e| e.airdate.as_str());
+            let season_premiere = match airdates.filter(|date| !date.is_empty()).min() {
+                Some(date) => Some(date),
+                None if season == 1 => show.premiered.as_deref(),
+                None => None,
+            };
+            announced_schedule(&show, season_premiere)
+        }
+    };
+    let premiered = show.premiered.as_deref().map(year).unwrap_or_default();
+    let release_date = match show.ended.as_deref().map(year) {
+        Some(ended) if ended != premiered => format!("{}–{}", premiered, ended),
+        _ => premiered.to_owned(),
+    };
+
+    Ok(ShowRecord {
+        title: show.name.clone(),
+        alternative_title: show.name.clone(),
+        release_date,
+        about: parsers::html_to_text(show.summary.as_deref().unwrap_or_default())?,
+        link_to_show: link.to_owned(),
+        link_to_picture: show
+            .image
+            .as_ref()
+            .and_then(|i| i.original.clone())
+            .unwrap_or_default(),
+        show_type: show_type(&show),
+        episodes_count: episodes.len() as i32,
+        schedule,
+        ..Default::default()
+    }
+    .into())
+}
+
+/// Reads a response of the show search API.
+pub fn parse_search(content: &str) -> Result<Vec<SearchResult>> {
+    let found: Vec<Found> =
+        serde_json::from_str(content).context("Failed to parse the TVmaze search response")?;
+    Ok(found
+        .into_iter()
+        .map(|Found { show }| SearchResult {
+            year: show
+                .premiered
+                .as_deref()
+                .map(year)
+                .unwrap_or_default()
+                .to_owned(),
+            show_type: show_type(&show),
+            picture: show
+                .image
+                .as_ref()
+                .and_then(|i| i.medium.clone().or(i.original.clone()))
+                .unwrap_or_default(),
+            title: show.name,
+            link: show.url,
+            ..Default::default()
+        })
+        .collect())
+}
+
+impl SiteParser for Tvmaze {
+    fn name(&self) -> &'static str {
+        "TVmaze"
+    }
+
+    fn matches(&self, url: &str) -> bool {
+        show_id(url).is_some()
+    }
+
+    fn fetch(&self, url: &str, _config: &Config) -> Result<ImportedShow> {
+        let Some(id) = show_id(url) else {
+            bail!("{} is not a TVmaze show", url);
+        };
+        let content = http::get(&format!("{}/shows/{}?embed=episodes", API, id), &[])?;
+        parse(&content, url)
+    }
+
+    fn search(&self, title: &str, _config: &Config) -> Result<Vec<SearchResult>> {
+        let content = http::get(
+            &format!("{}/search/shows?q={}", API, http::encode(title)),
+            &[],
+        )?;
+        parse_search(&content)
+    }
+}
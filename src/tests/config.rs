@@ -0,0 +1,93 @@
+use crate::{
+    config::{self, Config},
+    sites::{generic, SiteParser},
+};
+use std::path::PathBuf;
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(|a| a.to_string()).collect()
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!("watchlist-test-{}-{}", std::process::id(), name));
+    _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+#[test]
+fn database_arg_with_separate_value() {
+    let path = config::database_arg(args(&["--database", "/tmp/list.db"]));
+    assert_eq!(path, Some(PathBuf::from("/tmp/list.db")));
+}
+
+#[test]
+fn database_arg_with_equals_sign() {
+    let path = config::database_arg(args(&["--verbose", "--database=/tmp/list.db"]));
+    assert_eq!(path, Some(PathBuf::from("/tmp/list.db")));
+}
+
+#[test]
+fn database_arg_missing() {
+    assert_eq!(config::database_arg(args(&["--databases", "x"])), None);
+    assert_eq!(config::database_arg(args(&["--database"])), None);
+}
+
+#[test]
+fn database_override_priority() {
+    let config = Config {
+        database: Some(PathBuf::from("config.db")),
+        ..Default::default()
+    };
+    let arg = Some(PathBuf::from("arg.db"));
+    let env = Some(PathBuf::from("env.db"));
+
+    assert_eq!(
+        config::database_override(arg.clone(), env.clone(), &config),
+        arg
+    );
+    assert_eq!(config::database_override(None, env.clone(), &config), env);
+    assert_eq!(
+        config::database_override(None, None, &config),
+        Some(PathBuf::from("config.db"))
+    );
+    assert_eq!(
+        config::database_override(None, None, &Config::default()),
+        None
+    );
+}
+
+#[test]
+fn parse_config() {
+    let config = Config::parse("database = \"/srv/watchlist.db\"\n").unwrap();
+    assert_eq!(config.database, Some(PathBuf::from("/srv/watchlist.db")));
+    let config = Config::parse("tmdb_api_key = \"0123abcd\"\n").unwrap();
+    assert_eq!(config.tmdb_api_key.as_deref(), Some("0123abcd"));
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    let config = Config::parse("language = \"uk-UA\"\n[languages]\nimdb = \"en-US\"\n").unwrap();
+    assert_eq!(config.language("IMDb"), Some("en-US"));
+    assert_eq!(config.language("TMDB"), Some("uk-UA"));
+    let config = Config::parse("[languages]\n\"web page\" = \"de-DE\"\n").unwrap();
+    assert_eq!(config.language(generic::Generic.name()), Some("de-DE"));
+    assert_eq!(Config::default().language("IMDb"), None);
+    assert!(Config::parse("database = ").is_err());
+}
+
+#[test]
+fn move_legacy_database_once() {
+    let dir = temp_dir("legacy");
+    let legacy = dir.join("watchlist.db");
+    let target = dir.join("data").join("watchlist").join("watchlist.db");
+    std::fs::write(&legacy, b"legacy").unwrap();
+
+    assert!(config::move_legacy_database(&legacy, &target).unwrap());
+    assert!(!legacy.exists());
+    assert_eq!(std::fs::read(&target).unwrap(), b"legacy");
+
+    std::fs::write(&legacy, b"another").unwrap();
+    assert!(!config::move_legacy_database(&legacy, &target).unwrap());
+    assert_eq!(std::fs::read(&target).unwrap(), b"legacy");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
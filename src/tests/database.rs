@@ -0,0 +1,429 @@
+use crate::{
+    database::{self, Repository, WatchEvent},
+    model::{ShowRecord, Status},
+};
+use sqlite::Connection;
+
+/// The `list` table as it was created before schema migrations existed.
+const LEGACY_SCHEMA: &str = "CREATE TABLE list (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        title TEXT NOT NULL UNIQUE,
+        alternative_title TEXT,
+        release_date TEXT,
+        about TEXT,
+        link_to_show TEXT,
+        score INTEGER,
+        favorite BOOL,
+        status INTEGER,
+        image BLOB,
+        show_type INTEGER,
+        season INTEGER,
+        episodes_count INTEGER,
+        episode INTEGER,
+        release_time TEXT,
+        schedule_monday INTEGER,
+        schedule_tuesday INTEGER,
+        schedule_wednesday INTEGER,
+        schedule_thursday INTEGER,
+        schedule_friday INTEGER,
+        schedule_saturday INTEGER,
+        schedule_sunday INTEGER
+    );";
+
+fn user_version(connection: &Connection) -> usize {
+    database::schema_version(connection).unwrap()
+}
+
+#[test]
+fn migrate_fresh_database() {
+    let connection = sqlite::open(":memory:").unwrap();
+    database::migrate(&connection).unwrap();
+    assert_eq!(user_version(&connection), database::latest_schema_version());
+    connection
+        .execute("SELECT id, title, image FROM list;")
+        .unwrap();
+}
+
+#[test]
+fn migrate_is_idempotent() {
+    let connection = sqlite::open(":memory:").unwrap();
+    database::migrate(&connection).unwrap();
+    database::migrate(&connection).unwrap();
+    assert_eq!(user_version(&connection), database::latest_schema_version());
+}
+
+#[test]
+fn migrate_legacy_database_keeps_rows() {
+    let connection = sqlite::open(":memory:").unwrap();
+    connection
+        .execute(format!(
+            "{} INSERT INTO list(title) VALUES ('The Orville');",
+            LEGACY_SCHEMA
+        ))
+        .unwrap();
+
+    database::migrate(&connection).unwrap();
+    assert_eq!(user_version(&connection), database::latest_schema_version());
+
+    let mut statement = connection.prepare("SELECT title FROM list;").unwrap();
+    statement.next().unwrap();
+    assert_eq!(statement.read::<String, _>(0).unwrap(), "The Orville");
+}
+
+#[test]
+fn migrate_refuses_newer_database() {
+    let connection = sqlite::open(":memory:").unwrap();
+    let newer = database::latest_schema_version() + 1;
+    connection
+        .execute(format!("PRAGMA user_version = {};", newer))
+        .unwrap();
+    assert!(database::migrate(&connection).is_err());
+    assert_eq!(user_version(&connection), newer);
+}
+
+fn repository() -> Repository {
+    Repository::from_connection(sqlite::open(":memory:").unwrap()).unwrap()
+}
+
+fn repository_with_show() -> (Repository, i64) {
+    let repository = repository();
+    let show = ShowRecord {
+        title: "Frieren".into(),
+        season: 1,
+        ..Default::default()
+    };
+    let id = repository.save_show(&show).unwrap();
+    (repository, id)
+}
+
+#[test]
+fn migrate_seeds_watch_history_from_progress() {
+    let connection = sqlite::open(":memory:").unwrap();
+    connection
+        .execute(format!(
+            "{} INSERT INTO list(title, season, episode) VALUES ('Watched', 2, 5);
+             INSERT INTO list(title, season, episode) VALUES ('Planned', 1, 0);",
+            LEGACY_SCHEMA
+        ))
+        .unwrap();
+    let repository = Repository::from_connection(connection).unwrap();
+
+    let history = repository.watch_history(1).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!((history[0].season, history[0].episode), (2, 5));
+    assert_eq!(history[0].watched_at, None);
+    assert!(repository.watch_history(2).unwrap().is_empty());
+}
+
+#[test]
+fn record_watch_event_skips_unchanged_progress() {
+    let (repository, id) = repository_with_show();
+    repository.record_watch_event(id, 1, 1, None, None).unwrap();
+    repository.record_watch_event(id, 1, 1, None, None).unwrap();
+    repository
+        .record_watch_event(id, 1, 2, Some(8), Some("Great"))
+        .unwrap();
+
+    let history = repository.watch_history(id).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].episode, 2);
+    assert_eq!(history[1].rating, Some(8));
+    assert_eq!(history[1].note.as_deref(), Some("Great"));
+    assert!(history[1].watched_at.is_some());
+}
+
+#[test]
+fn rollback_restores_previous_progress() {
+    let (repository, id) = repository_with_show();
+    repository.record_watch_event(id, 1, 1, None, None).unwrap();
+    repository.record_watch_event(id, 1, 2, None, None).unwrap();
+    repository.record_watch_event(id, 2, 1, None, None).unwrap();
+
+    let removed = repository.rollback_last_watch_event(id).unwrap();
+    assert_eq!(removed.map(|e| (e.season, e.episode)), Some((2, 1)));
+    assert_eq!(repository.recompute_progress(id).unwrap(), (1, 2));
+
+    let show = &repository.load_shows().unwrap()[0];
+    assert_eq!((show.season, show.episode), (1, 2));
+}
+
+#[test]
+fn rollback_of_empty_history_resets_progress() {
+    let (repository, id) = repository_with_show();
+    assert_eq!(repository.rollback_last_watch_event(id).unwrap(), None);
+    assert_eq!(repository.recompute_progress(id).unwrap(), (1, 0));
+}
+
+const TRICKY_TITLES: [&str; 4] = [
+    "The \"Quoted\" Show",
+    "Robert'); DROP TABLE list; --",
+    "Title \" OR \"1\" = \"1",
+    "Реінкарнація безробітного 無職転生",
+];
+
+fn count_rows(connection: &Connection) -> i64 {
+    let mut statement = connection.prepare("SELECT COUNT(*) FROM list;").unwrap();
+    statement.next().unwrap();
+    statement.read::<i64, _>(0).unwrap()
+}
+
+fn read_show(connection: &Connection, id: i64) -> (String, i64, i64, i64) {
+    let mut statement = connection
+        .prepare("SELECT title, score, status, favorite FROM list WHERE id = ?;")
+        .unwrap();
+    statement.bind((1, id)).unwrap();
+    statement.next().unwrap();
+    (
+        statement.read::<String, _>(0).unwrap(),
+        statement.read::<i64, _>(1).unwrap(),
+        statement.read::<i64, _>(2).unwrap(),
+        statement.read::<i64, _>(3).unwrap(),
+    )
+}
+
+fn save_tricky_titles(repository: &Repository) -> Vec<i64> {
+    TRICKY_TITLES
+        .iter()
+        .map(|title| {
+            let show = ShowRecord {
+                title: (*title).into(),
+                ..Default::default()
+            };
+            repository.save_show(&show).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn save_show_with_tricky_titles() {
+    let repository = repository();
+    let ids = save_tricky_titles(&repository);
+
+    assert_eq!(
+        count_rows(repository.connection()),
+        TRICKY_TITLES.len() as i64
+    );
+    for (id, title) in ids.iter().zip(TRICKY_TITLES) {
+        assert_eq!(read_show(repository.connection(), *id).0, title);
+    }
+}
+
+#[test]
+fn update_show_with_tricky_titles() {
+    let repository = repository();
+    let ids = save_tricky_titles(&repository);
+
+    for id in &ids {
+        repository.update_score(*id, 7).unwrap();
+        repository.update_status(*id, Status::Completed).unwrap();
+        repository.update_favorite(*id, true).unwrap();
+        repository.update_progress(*id, 2, 3).unwrap();
+    }
+
+    for (id, title) in ids.iter().zip(TRICKY_TITLES) {
+        assert_eq!(
+            read_show(repository.connection(), *id),
+            (title.to_string(), 7, 2, 1)
+        );
+    }
+}
+
+#[test]
+fn delete_show_with_tricky_titles_removes_only_that_show() {
+    let repository = repository();
+    let ids = save_tricky_titles(&repository);
+
+    for (deleted, id) in ids.iter().enumerate() {
+        repository.delete_show(*id).unwrap();
+        assert_eq!(
+            count_rows(repository.connection()),
+            (TRICKY_TITLES.len() - deleted - 1) as i64
+        );
+    }
+}
+
+#[test]
+fn migrate_normalizes_favorite_flags() {
+    let connection = sqlite::open(":memory:").unwrap();
+    connection
+        .execute(format!(
+            "{} INSERT INTO list(title, favorite) VALUES ('A', 'true');
+             INSERT INTO list(title, favorite) VALUES ('B', 'false');
+             INSERT INTO list(title, favorite) VALUES ('C', 1);
+             INSERT INTO list(title, favorite) VALUES ('D', NULL);",
+            LEGACY_SCHEMA
+        ))
+        .unwrap();
+    database::migrate(&connection).unwrap();
+
+    let mut statement = connection
+        .prepare("SELECT favorite FROM list ORDER BY id;")
+        .unwrap();
+    let mut favorites = Vec::new();
+    while let sqlite::State::Row = statement.next().unwrap() {
+        favorites.push(statement.read::<i64, _>(0).unwrap());
+    }
+    assert_eq!(favorites, [1, 0, 1, 0]);
+}
+
+fn picture_file(name: &str, width: u32, height: u32) -> std::path::PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push(format!(
+        "watchlist-test-{}-{}.png",
+        std::process::id(),
+        name
+    ));
+    image::RgbaImage::from_pixel(width, height, image::Rgba([200, 40, 40, 255]))
+        .save(&path)
+        .unwrap();
+    path
+}
+
+fn read_blob(connection: &Connection, column: &str, id: i64) -> (String, Vec<u8>) {
+    let mut statement = connection
+        .prepare(format!(
+            "SELECT typeof({0}), {0} FROM list WHERE id = ?;",
+            column
+        ))
+        .unwrap();
+    statement.bind((1, id)).unwrap();
+    statement.next().unwrap();
+    (
+        statement.read::<String, _>(0).unwrap(),
+        statement.read::<Vec<u8>, _>(1).unwrap(),
+    )
+}
+
+#[test]
+fn save_show_stores_picture_and_thumbnail() {
+    let repository = repository();
+    let path = picture_file("save", 600, 900);
+    let show = ShowRecord {
+        title: "Poster".into(),
+        link_to_picture: path.to_str().unwrap().to_owned(),
+        ..Default::default()
+    };
+    let id = repository.save_show(&show).unwrap();
+
+    let (kind, image) = read_blob(repository.connection(), "image", id);
+    assert_eq!(kind, "blob");
+    assert_eq!(image, std::fs::read(&path).unwrap());
+    assert_eq!(repository.read_picture(id).unwrap(), Some(image));
+    assert_eq!(repository.load_thumbnails().unwrap().len(), 1);
+
+    let (kind, thumbnail) = read_blob(repository.connection(), "thumbnail", id);
+    assert_eq!(kind, "blob");
+    let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+    assert_eq!((thumbnail.width(), thumbnail.height()), (200, 300));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn migrate_converts_text_pictures_to_blobs() {
+    let connection = sqlite::open(":memory:").unwrap();
+    connection.execute(LEGACY_SCHEMA).unwrap();
+    let path = picture_file("legacy", 40, 60);
+    let content = std::fs::read(&path).unwrap();
+    let mut statement = connection
+        .prepare("INSERT INTO list(title, image) VALUES ('Legacy', CAST(? AS TEXT));")
+        .unwrap();
+    statement.bind((1, content.as_slice())).unwrap();
+    statement.next().unwrap();
+    drop(statement);
+    assert_eq!(read_blob(&connection, "image", 1).0, "text");
+
+    database::migrate(&connection).unwrap();
+    assert_eq!(
+        read_blob(&connection, "image", 1),
+        ("blob".to_string(), content)
+    );
+    assert_eq!(read_blob(&connection, "thumbnail", 1).0, "blob");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn delete_show_removes_watch_history() {
+    let (repository, id) = repository_with_show();
+    repository.update_progress(id, 1, 1).unwrap();
+    repository.update_progress(id, 1, 2).unwrap();
+    assert_eq!(repository.watch_history(id).unwrap().len(), 2);
+
+    repository.delete_show(id).unwrap();
+    assert!(repository.watch_history(id).unwrap().is_empty());
+}
+
+#[test]
+fn save_show_refuses_a_taken_title() {
+    let (repository, id) = repository_with_show();
+    repository.update_progress(id, 1, 1).unwrap();
+    let duplicate = ShowRecord {
+        title: "Frieren".into(),
+        season: 2,
+        ..Default::default()
+    };
+    let error = repository.save_show(&duplicate).unwrap_err();
+    assert_eq!(error.to_string(), "\"Frieren\" is already in the watchlist");
+
+    assert_eq!(repository.load_shows().unwrap().len(), 1);
+    assert_eq!(repository.load_show(id).unwrap().season, 1);
+    assert_eq!(repository.watch_history(id).unwrap().len(), 1);
+}
+
+#[test]
+fn undo_progress_restores_previous_episode() {
+    let (repository, id) = repository_with_show();
+    repository.update_progress(id, 1, 1).unwrap();
+    repository.update_progress(id, 1, 2).unwrap();
+
+    assert_eq!(repository.undo_progress(id).unwrap(), (1, 1));
+    let show = &repository.load_shows().unwrap()[0];
+    assert_eq!((show.season, show.episode), (1, 1));
+}
+
+#[test]
+fn failed_transaction_is_rolled_back() {
+    let (repository, id) = repository_with_show();
+    let result: anyhow::Result<()> = repository.transaction(|| {
+        repository.update_score(id, 9)?;
+        anyhow::bail!("Interrupted")
+    });
+    assert!(result.is_err());
+    assert_eq!(repository.load_shows().unwrap()[0].score, 0);
+}
+
+#[test]
+fn imported_watch_events_are_merged_by_time() {
+    let (repository, id) = repository_with_show();
+    repository.update_progress(id, 1, 1).unwrap();
+    let event = |episode, watched_at: &str| WatchEvent {
+        season: 1,
+        episode,
+        watched_at: Some(watched_at.into()),
+        ..Default::default()
+    };
+
+    repository
+        .import_watch_events(
+            id,
+            &[
+                event(3, "2001-01-03 20:00:00"),
+                event(2, "2001-01-02 20:00:00"),
+                event(2, "2001-01-02 21:00:00"),
+            ],
+        )
+        .unwrap();
+    let history = repository.watch_history(id).unwrap();
+    let episodes = history.iter().map(|e| e.episode).collect::<Vec<_>>();
+    assert_eq!(episodes, vec![2, 3, 1]);
+    assert_eq!(
+        history[0].watched_at.as_deref(),
+        Some("2001-01-02 20:00:00")
+    );
+
+    repository
+        .import_watch_events(id, &[event(3, "2001-01-03 20:00:00")])
+        .unwrap();
+    assert_eq!(repository.watch_history(id).unwrap().len(), 3);
+}
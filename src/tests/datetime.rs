@@ -112,3 +112,37 @@ fn check_new_episodes_not_available_2_per_week_with_episode_greather_then_max()
     );
     assert!(!datetime::check_new_episodes_available(&data.0, current_episode, data.1).unwrap());
 }
+
+#[test]
+fn releases_end_with_the_last_episode() {
+    // Monday and Thursday, two episodes on Thursday.
+    let schedule = [1, 0, 0, 2, 0, 0, 0];
+    let releases = datetime::releases("2024-03-25 18:00", schedule, 5)
+        .unwrap()
+        .map(|r| {
+            (
+                r.time.format("%Y-%m-%d %H:%M").to_string(),
+                r.first_episode,
+                r.last_episode,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        releases,
+        vec![
+            ("2024-03-25 18:00".to_string(), 1, 1),
+            ("2024-03-28 18:00".to_string(), 2, 3),
+            ("2024-04-01 18:00".to_string(), 4, 4),
+            ("2024-04-04 18:00".to_string(), 5, 5),
+        ]
+    );
+
+    let endless = datetime::releases("2024-03-25 18:00", schedule, 0).unwrap();
+    assert_eq!(endless.take(100).count(), 100);
+    assert_eq!(
+        datetime::releases("2024-03-25 18:00", [0; 7], 0)
+            .unwrap()
+            .count(),
+        0
+    );
+}
@@ -0,0 +1,543 @@
+use crate::{
+    database::Repository,
+    formats::{self, csv, ics, json::Export, Format, SCHEMA_VERSION},
+    model::{Schedule, ShowRecord, ShowType, Status},
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use chrono::TimeZone;
+use std::path::Path;
+
+fn png() -> Vec<u8> {
+    let mut content = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(4, 6, image::Rgba([40, 200, 40, 255]))
+        .write_to(&mut content, image::ImageFormat::Png)
+        .unwrap();
+    content.into_inner()
+}
+
+fn parse(content: &str, format: Format) -> Vec<ShowRecord> {
+    let parsed = formats::parse(content, format).unwrap();
+    assert!(parsed.unmatched.is_empty());
+    parsed.shows.into_iter().map(|s| s.show).collect()
+}
+
+fn repository_with_shows() -> Repository {
+    let repository = Repository::from_connection(sqlite::open(":memory:").unwrap()).unwrap();
+    let orville = ShowRecord {
+        title: "The Orville".into(),
+        about: "Captain Ed Mercer, \"a\" ship,\nand a crew".into(),
+        score: 8,
+        favorite: true,
+        status: Status::Watching,
+        season: 2,
+        episodes_count: 14,
+        episode: 3,
+        schedule: Schedule {
+            release_time: "2017-09-10 20:00".into(),
+            episodes_per_weekday: [0, 0, 0, 1, 0, 0, 0],
+        },
+        ..Default::default()
+    };
+    let id = repository.save_show(&orville).unwrap();
+    repository.update_picture(id, &png()).unwrap();
+    let your_name = ShowRecord {
+        title: "Your Name.".into(),
+        show_type: ShowType::Anime,
+        season: 1,
+        ..Default::default()
+    };
+    repository.save_show(&your_name).unwrap();
+    repository
+}
+
+#[test]
+fn format_from_path() {
+    assert_eq!(
+        Format::from_path(Path::new("list.JSON")).unwrap(),
+        Format::Json
+    );
+    assert_eq!(
+        Format::from_path(Path::new("dir/list.csv")).unwrap(),
+        Format::Csv
+    );
+    assert!(Format::from_path(Path::new("list.txt")).is_err());
+    assert!(Format::from_path(Path::new("list")).is_err());
+}
+
+#[test]
+fn json_export_keeps_every_field() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::Json, false).unwrap();
+    let export: Export = serde_json::from_str(&content).unwrap();
+
+    assert_eq!(export.schema_version, SCHEMA_VERSION);
+    let mut stored = repository.load_shows().unwrap();
+    stored.sort_by_key(|s| s.id);
+    let shows = export.shows.into_iter().map(|s| s.show).collect::<Vec<_>>();
+    assert_eq!(shows, stored);
+    assert!(content.contains("\"status\": \"watching\""));
+    assert!(!content.contains("\"picture\""));
+}
+
+#[test]
+fn json_export_embeds_pictures_on_request() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::Json, true).unwrap();
+    let export: Export = serde_json::from_str(&content).unwrap();
+
+    let picture = export.shows[0].picture.as_ref().unwrap();
+    assert_eq!(BASE64_STANDARD.decode(picture).unwrap(), png());
+    assert_eq!(export.shows[1].picture, None);
+}
+
+#[test]
+fn csv_export_quotes_fields() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::Csv, false).unwrap();
+
+    assert!(content.starts_with(&csv::COLUMNS.join(",")));
+    assert!(content.contains(
+        "1,1,The Orville,,,\"Captain Ed Mercer, \"\"a\"\" ship,\nand a crew\",,8,true,watching,serial,2,14,3,2017-09-10 20:00,0,0,0,1,0,0,0\n"
+    ));
+    assert!(
+        content.ends_with("1,2,Your Name.,,,,,0,false,watch-later,anime,1,0,0,,0,0,0,0,0,0,0\n")
+    );
+}
+
+#[test]
+fn csv_records_follow_rfc_4180() {
+    let content = "\u{feff}a,\"b, \"\"quoted\"\"\",c\r\n\r\n\"multi\nline\",,\n";
+    assert_eq!(
+        csv::parse_records(content).unwrap(),
+        vec![vec!["a", "b, \"quoted\"", "c"], vec!["multi\nline", "", ""],]
+    );
+    assert!(csv::parse_records("a,\"b").is_err());
+}
+
+#[test]
+fn csv_export_round_trips() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::Csv, false).unwrap();
+    let shows = parse(&content, Format::Csv);
+
+    let mut stored = repository.load_shows().unwrap();
+    stored.sort_by_key(|s| s.id);
+    assert_eq!(shows, stored);
+}
+
+#[test]
+fn csv_export_round_trips_multi_line_text() {
+    let repository = repository_with_shows();
+    let mut show = repository.load_show(1).unwrap();
+    show.about = " Captain Ed Mercer,\n\nand a crew.\n".into();
+    repository.save_show(&show).unwrap();
+    let content = formats::export(&repository, Format::Csv, false).unwrap();
+    let shows = parse(&content, Format::Csv);
+
+    assert_eq!(shows[0].about, " Captain Ed Mercer,\n\nand a crew.\n");
+    assert_eq!(shows[0], repository.load_show(1).unwrap());
+}
+
+#[test]
+fn json_export_round_trips_pictures() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::Json, true).unwrap();
+    let shows = formats::parse(&content, Format::Json).unwrap().shows;
+
+    assert_eq!(shows[0].picture, Some(png()));
+    assert_eq!(shows[1].picture, None);
+}
+
+#[test]
+fn newer_schema_versions_are_rejected() {
+    let json = format!(
+        "{{\"schema_version\": {}, \"shows\": []}}",
+        SCHEMA_VERSION + 1
+    );
+    assert!(formats::parse(&json, Format::Json).is_err());
+    let csv = format!("schema_version,title\n{},Frieren\n", SCHEMA_VERSION + 1);
+    assert!(formats::parse(&csv, Format::Csv).is_err());
+}
+
+const ANIMELIST: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<myanimelist>
+    <myinfo>
+        <user_export_type>1</user_export_type>
+    </myinfo>
+    <anime>
+        <series_animedb_id>52991</series_animedb_id>
+        <series_title><![CDATA[Sousou no Frieren]]></series_title>
+        <series_type>TV</series_type>
+        <series_episodes>28</series_episodes>
+        <my_watched_episodes>12</my_watched_episodes>
+        <my_score>9</my_score>
+        <my_status>Watching</my_status>
+        <update_on_import>1</update_on_import>
+    </anime>
+    <anime>
+        <series_animedb_id>32281</series_animedb_id>
+        <series_title><![CDATA[Kimi no Na wa.]]></series_title>
+        <series_episodes>1</series_episodes>
+        <my_watched_episodes>0</my_watched_episodes>
+        <my_score>0</my_score>
+        <my_status>Plan to Watch</my_status>
+    </anime>
+    <anime>
+        <series_animedb_id>0</series_animedb_id>
+        <series_title>Dungeon Meshi</series_title>
+        <series_episodes>24</series_episodes>
+        <my_watched_episodes>3</my_watched_episodes>
+        <my_score>7</my_score>
+        <my_status>4</my_status>
+    </anime>
+</myanimelist>
+"#;
+
+#[test]
+fn mal_import_maps_fields() {
+    let shows = parse(ANIMELIST, Format::MyAnimeList);
+
+    assert_eq!(
+        shows[0],
+        ShowRecord {
+            title: "Sousou no Frieren".into(),
+            link_to_show: "https://myanimelist.net/anime/52991".into(),
+            score: 9,
+            status: Status::Watching,
+            show_type: ShowType::Anime,
+            season: 1,
+            episodes_count: 28,
+            episode: 12,
+            ..Default::default()
+        }
+    );
+    assert_eq!(shows[1].status, Status::WatchLater);
+    assert_eq!(
+        (shows[2].status, shows[2].link_to_show.as_str()),
+        (Status::Dropped, "")
+    );
+    assert!(formats::parse("<animelist/>", Format::MyAnimeList).is_err());
+}
+
+#[test]
+fn mal_export_round_trips_anime() {
+    let repository = repository_with_shows();
+    let content = formats::export(&repository, Format::MyAnimeList, false).unwrap();
+    assert!(content.contains("<user_total_anime>1</user_total_anime>"));
+    assert!(content.contains("<series_title><![CDATA[Your Name.]]></series_title>"));
+    assert!(content.contains("<my_status>Plan to Watch</my_status>"));
+    assert!(!content.contains("The Orville"));
+
+    let shows = parse(&content, Format::MyAnimeList);
+    assert_eq!(shows.len(), 1);
+    assert_eq!(shows[0].title, "Your Name.");
+
+    let shows = parse(ANIMELIST, Format::MyAnimeList);
+    let exported = formats::mal::to_string(&shows);
+    assert!(exported.contains("<series_animedb_id>52991</series_animedb_id>"));
+    assert_eq!(parse(&exported, Format::MyAnimeList), shows);
+}
+
+const LETTERBOXD_DIARY: &str = "Date,Name,Year,Letterboxd URI,Rating,Rewatch,Tags,Watched Date
+2023-01-02,Your Name.,2016,https://boxd.it/3TjA3t,4.5,,,2023-01-01
+2023-02-10,Perfect Blue,1997,https://boxd.it/3Uh9RZ,4,,,2023-02-10
+2024-05-05,Your Name.,2016,https://boxd.it/6Kq2Xb,,Yes,,2024-05-04
+";
+
+const IMDB_RATINGS: &str = "Const,Your Rating,Date Rated,Title,Original Title,URL,Title Type,IMDb Rating,Runtime (mins),Year,Genres,Num Votes,Release Date,Directors
+tt5311514,9,2023-01-02,Your Name.,Kimi no na wa.,https://www.imdb.com/title/tt5311514/,Movie,8.4,106,2016,\"Animation, Drama\",300000,2016-08-26,Makoto Shinkai
+tt5691552,8,2023-03-01,The Orville,The Orville,https://www.imdb.com/title/tt5691552/,TV Series,8.0,44,2017,\"Comedy, Drama\",100000,2017-09-10,
+tt0000001,7,2023-03-02,Old Wounds,Old Wounds,https://www.imdb.com/title/tt0000001/,TV Episode,8.0,44,2017,Drama,1000,2017-09-10,
+";
+
+#[test]
+fn csv_of_other_sites_is_detected_by_header() {
+    let path = Path::new("export.csv");
+    assert_eq!(
+        Format::detect(path, LETTERBOXD_DIARY).unwrap(),
+        Format::Letterboxd
+    );
+    assert_eq!(
+        Format::detect(path, IMDB_RATINGS).unwrap(),
+        Format::ImdbRatings
+    );
+    assert_eq!(
+        Format::detect(path, "schema_version,title\n").unwrap(),
+        Format::Csv
+    );
+    assert_eq!(
+        Format::detect(Path::new("animelist.xml"), "").unwrap(),
+        Format::MyAnimeList
+    );
+}
+
+#[test]
+fn letterboxd_import_keeps_the_latest_entry_of_a_film() {
+    let shows = parse(LETTERBOXD_DIARY, Format::Letterboxd);
+
+    assert_eq!(shows.len(), 2);
+    assert_eq!(
+        shows[0],
+        ShowRecord {
+            title: "Your Name.".into(),
+            release_date: "2016".into(),
+            link_to_show: "https://boxd.it/6Kq2Xb".into(),
+            score: 9,
+            status: Status::Completed,
+            show_type: ShowType::Film,
+            season: 1,
+            episodes_count: 1,
+            episode: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(shows[1].score, 8);
+}
+
+#[test]
+fn imdb_ratings_import_skips_episodes() {
+    let shows = parse(IMDB_RATINGS, Format::ImdbRatings);
+
+    assert_eq!(shows.len(), 2);
+    assert_eq!(
+        shows[0],
+        ShowRecord {
+            title: "Your Name.".into(),
+            alternative_title: "Kimi no na wa.".into(),
+            release_date: "2016-08-26".into(),
+            link_to_show: "https://www.imdb.com/title/tt5311514/".into(),
+            score: 9,
+            status: Status::Completed,
+            show_type: ShowType::Film,
+            season: 1,
+            episodes_count: 1,
+            episode: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        (
+            shows[1].show_type,
+            shows[1].alternative_title.as_str(),
+            shows[1].episode
+        ),
+        (ShowType::Serial, "", 0)
+    );
+    assert!(formats::export(&repository_with_shows(), Format::ImdbRatings, false).is_err());
+}
+
+const TRAKT_WATCHED_SHOWS: &str = r#"[
+  {
+    "plays": 3,
+    "last_watched_at": "2024-01-03T20:00:00.000Z",
+    "show": {
+      "title": "The Orville",
+      "year": 2017,
+      "ids": { "trakt": 118164, "slug": "the-orville", "imdb": "tt5691552", "tmdb": 71738 }
+    },
+    "seasons": [
+      {
+        "number": 1,
+        "episodes": [
+          { "number": 1, "plays": 1, "last_watched_at": "2024-01-01T20:00:00.000Z" },
+          { "number": 3, "plays": 1, "last_watched_at": "2024-01-03T20:00:00.000Z" },
+          { "number": 2, "plays": 1, "last_watched_at": "2024-01-02T20:00:00.000Z" }
+        ]
+      }
+    ]
+  }
+]"#;
+
+const TRAKT_WATCHED_MOVIES: &str = r#"[
+  {
+    "plays": 1,
+    "last_watched_at": "2023-06-01T18:30:00.000Z",
+    "movie": { "title": "Perfect Blue", "year": 1997, "ids": { "trakt": 1, "slug": "perfect-blue-1997" } }
+  }
+]"#;
+
+const TRAKT_WATCHLIST: &str = r#"[
+  {
+    "rank": 1,
+    "listed_at": "2024-02-01T10:00:00.000Z",
+    "type": "show",
+    "show": { "title": "Frieren", "year": 2023, "ids": { "trakt": 2 } }
+  }
+]"#;
+
+const TRAKT_RATINGS: &str = r#"[
+  { "rated_at": "2024-01-04T00:00:00.000Z", "rating": 8, "type": "show",
+    "show": { "title": "The Orville", "year": 2017, "ids": { "trakt": 118164 } } },
+  { "rated_at": "2024-01-04T00:00:00.000Z", "rating": 9, "type": "episode",
+    "episode": { "season": 1, "number": 2 },
+    "show": { "title": "The Orville", "year": 2017, "ids": { "trakt": 118164 } } },
+  { "rated_at": "2024-01-04T00:00:00.000Z", "rating": 7, "type": "season",
+    "season": { "number": 1 },
+    "show": { "title": "The Orville", "year": 2017, "ids": { "trakt": 118164 } } },
+  { "rated_at": "2024-01-04T00:00:00.000Z", "rating": 6, "type": "movie",
+    "movie": { "title": "Paprika", "year": 2006, "ids": { "trakt": 3 } } }
+]"#;
+
+#[test]
+fn trakt_backup_import() {
+    let mut directory = std::env::temp_dir();
+    directory.push(format!("watchlist-test-{}-trakt", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    for (name, content) in [
+        ("watched-shows.json", TRAKT_WATCHED_SHOWS),
+        ("watched-movies.json", TRAKT_WATCHED_MOVIES),
+        ("watchlist-shows.json", TRAKT_WATCHLIST),
+        ("ratings.json", TRAKT_RATINGS),
+        ("user-settings.json", "{\"user\": {}}"),
+    ] {
+        std::fs::write(directory.join(name), content).unwrap();
+    }
+
+    let parsed = formats::read(&directory.join("watched-shows.json"), None).unwrap();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    // Files are read in the order of their names.
+    let shows = &parsed.shows;
+    assert_eq!(shows.len(), 3);
+    let orville = &shows[1];
+    assert_eq!(
+        orville.show,
+        ShowRecord {
+            title: "The Orville".into(),
+            release_date: "2017".into(),
+            link_to_show: "https://www.imdb.com/title/tt5691552/".into(),
+            score: 8,
+            status: Status::Watching,
+            season: 1,
+            episode: 3,
+            ..Default::default()
+        }
+    );
+    let history = orville
+        .history
+        .iter()
+        .map(|e| (e.episode, e.rating, e.watched_at.is_some()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        history,
+        vec![(1, None, true), (2, Some(9), true), (3, None, true)]
+    );
+
+    let movie = &shows[0].show;
+    assert_eq!(
+        (
+            movie.title.as_str(),
+            movie.status,
+            movie.show_type,
+            movie.episode
+        ),
+        ("Perfect Blue", Status::Completed, ShowType::Film, 1)
+    );
+    assert_eq!(
+        movie.link_to_show,
+        "https://trakt.tv/movies/perfect-blue-1997"
+    );
+    assert_eq!(
+        (shows[2].show.status, shows[2].show.episode),
+        (Status::WatchLater, 0)
+    );
+
+    assert_eq!(parsed.unmatched.len(), 2);
+    assert!(parsed.unmatched[0].contains("season 1 of \"The Orville\""));
+    assert!(parsed.unmatched[1].contains("\"Paprika\" (2006)"));
+}
+
+fn calendar_shows() -> Vec<ShowRecord> {
+    let show =
+        |id, title: &str, status, release_time: &str, episodes_per_weekday, episodes_count| {
+            ShowRecord {
+                id,
+                title: title.into(),
+                status,
+                episodes_count,
+                schedule: Schedule {
+                    release_time: release_time.into(),
+                    episodes_per_weekday,
+                },
+                ..Default::default()
+            }
+        };
+    vec![
+        // Mondays and Thursdays from March 25, so episodes 3 to 6 are still to come.
+        show(
+            1,
+            "Kaiju No. 8, dubbed",
+            Status::Watching,
+            "2024-03-25 18:00",
+            [1, 0, 0, 1, 0, 0, 0],
+            6,
+        ),
+        show(
+            2,
+            "One Piece",
+            Status::Watching,
+            "2024-03-31 09:30",
+            [0, 0, 0, 0, 0, 0, 2],
+            0,
+        ),
+        show(
+            3,
+            "Frieren",
+            Status::WatchLater,
+            "2024-03-25 18:00",
+            [1, 0, 0, 0, 0, 0, 0],
+            0,
+        ),
+        show(4, "The Orville", Status::Watching, "", [0; 7], 0),
+        show(
+            5,
+            "Finished",
+            Status::Watching,
+            "2024-01-01 18:00",
+            [1, 0, 0, 0, 0, 0, 0],
+            2,
+        ),
+    ]
+}
+
+fn calendar_lines(expanded: bool) -> Vec<String> {
+    let now = chrono::Local
+        .with_ymd_and_hms(2024, 4, 1, 12, 0, 0)
+        .unwrap();
+    let content = ics::to_string(&calendar_shows(), expanded, now);
+    assert!(content.ends_with("END:VCALENDAR\r\n"));
+    content
+        .split("\r\n")
+        .filter(|line| !line.starts_with("DTSTAMP"))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn calendar_repeats_watched_shows_weekly() {
+    let lines = calendar_lines(false);
+    let events = lines.join("\n");
+    assert_eq!(events.matches("BEGIN:VEVENT").count(), 2);
+    assert!(events.contains(
+        "UID:watchlist-1@watchlist\nDTSTART:20240401T180000\n\
+         RRULE:FREQ=WEEKLY;BYDAY=MO,TH;COUNT=4\nSUMMARY:Kaiju No. 8\\, dubbed"
+    ));
+    assert!(events.contains(
+        "UID:watchlist-2@watchlist\nDTSTART:20240407T093000\n\
+         RRULE:FREQ=WEEKLY;BYDAY=SU\nSUMMARY:One Piece"
+    ));
+}
+
+#[test]
+fn calendar_expands_every_release() {
+    let lines = calendar_lines(true);
+    let summaries = lines
+        .iter()
+        .filter_map(|line| line.strip_prefix("SUMMARY:"))
+        .collect::<Vec<_>>();
+    assert_eq!(summaries.len(), 4 + ics::EXPANDED_WEEKS as usize);
+    assert_eq!(summaries[0], "Kaiju No. 8\\, dubbed: episode 3");
+    assert_eq!(summaries[3], "Kaiju No. 8\\, dubbed: episode 6");
+    assert_eq!(summaries[4], "One Piece: episodes 3-4");
+    assert!(lines.contains(&"DTSTART:20240404T180000".to_string()));
+}
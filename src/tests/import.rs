@@ -0,0 +1,209 @@
+use crate::{
+    database::{Repository, WatchEvent},
+    import::{self, Change, ImportSummary, IncomingShow, Resolution},
+    model::{ShowRecord, Status},
+};
+
+fn repository() -> Repository {
+    let repository = Repository::from_connection(sqlite::open(":memory:").unwrap()).unwrap();
+    let frieren = ShowRecord {
+        title: "Frieren".into(),
+        about: "An elf mage".into(),
+        status: Status::Watching,
+        season: 1,
+        episode: 10,
+        episodes_count: 28,
+        ..Default::default()
+    };
+    repository.save_show(&frieren).unwrap();
+    repository
+}
+
+fn incoming(show: ShowRecord) -> IncomingShow {
+    IncomingShow {
+        show,
+        ..Default::default()
+    }
+}
+
+fn frieren(repository: &Repository) -> ShowRecord {
+    repository
+        .load_shows()
+        .unwrap()
+        .into_iter()
+        .find(|s| s.title == "Frieren")
+        .unwrap()
+}
+
+#[test]
+fn preview_classifies_shows() {
+    let repository = repository();
+    let local = frieren(&repository);
+    let items = import::preview(
+        &repository,
+        vec![
+            incoming(ShowRecord {
+                title: "Dungeon Meshi".into(),
+                ..Default::default()
+            }),
+            incoming(ShowRecord {
+                title: "frieren".into(),
+                ..local.clone()
+            }),
+            incoming(ShowRecord {
+                title: "Dungeon Meshi".into(),
+                score: 9,
+                ..Default::default()
+            }),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].change, Change::New);
+    assert_eq!(items[1].change, Change::Unchanged);
+    assert_eq!(items[1].result(), None);
+}
+
+#[test]
+fn filling_blanks_and_moving_forward_is_not_a_conflict() {
+    let repository = repository();
+    let items = import::preview(
+        &repository,
+        vec![incoming(ShowRecord {
+            title: "Frieren".into(),
+            score: 10,
+            season: 1,
+            episode: 12,
+            ..Default::default()
+        })],
+    )
+    .unwrap();
+
+    assert_eq!(items[0].change, Change::Changed);
+    assert_eq!(items[0].changed_fields(), vec!["score", "progress"]);
+    import::apply(&repository, &items).unwrap();
+
+    let show = frieren(&repository);
+    assert_eq!(
+        (show.score, show.episode, show.about.as_str()),
+        (10, 12, "An elf mage")
+    );
+    assert_eq!(repository.watch_history(show.id).unwrap().len(), 2);
+}
+
+#[test]
+fn new_watch_history_changes_an_unchanged_show() {
+    let repository = repository();
+    let local = frieren(&repository);
+    let watched = |episode: i32, watched_at: &str| WatchEvent {
+        season: 1,
+        episode,
+        watched_at: Some(watched_at.into()),
+        ..Default::default()
+    };
+    let show = IncomingShow {
+        history: vec![
+            watched(9, "2024-01-05 21:00:00"),
+            watched(10, "2024-01-12 21:00:00"),
+        ],
+        ..incoming(local.clone())
+    };
+    let items = import::preview(&repository, vec![show.clone()]).unwrap();
+
+    assert_eq!(items[0].change, Change::Changed);
+    assert_eq!(items[0].new_events, 2);
+    assert_eq!(items[0].changed_fields(), vec!["history"]);
+    import::apply(&repository, &items).unwrap();
+    let history = repository.watch_history(local.id).unwrap();
+    assert!(history
+        .iter()
+        .any(|e| e.watched_at.as_deref() == Some("2024-01-05 21:00:00")));
+    assert_eq!(frieren(&repository), local);
+
+    let items = import::preview(&repository, vec![show]).unwrap();
+    assert_eq!(items[0].change, Change::Unchanged);
+    assert_eq!(items[0].new_events, 0);
+}
+
+#[test]
+fn conflicts_are_resolved_per_show() {
+    let repository = repository();
+    let conflicting = ShowRecord {
+        title: "Frieren".into(),
+        about: "Beyond journey's end".into(),
+        status: Status::Completed,
+        season: 1,
+        episode: 28,
+        ..Default::default()
+    };
+    let behind = ShowRecord {
+        episode: 4,
+        ..conflicting.clone()
+    };
+
+    let mut items = import::preview(&repository, vec![incoming(conflicting.clone())]).unwrap();
+    assert_eq!(items[0].change, Change::Conflict);
+    assert_eq!(items[0].conflicts, vec!["about", "status"]);
+    assert_eq!(items[0].resolution, Resolution::KeepLocal);
+    assert_eq!(
+        import::apply(&repository, &items).unwrap(),
+        ImportSummary {
+            skipped: 1,
+            ..Default::default()
+        }
+    );
+    assert_eq!(frieren(&repository).about, "An elf mage");
+
+    items[0].resolution = Resolution::KeepNewerProgress;
+    let show = items[0].result().unwrap();
+    assert_eq!(
+        (show.about.as_str(), show.status, show.episode),
+        ("An elf mage", Status::Watching, 28)
+    );
+
+    let mut items = import::preview(&repository, vec![incoming(behind)]).unwrap();
+    assert_eq!(items[0].conflicts, vec!["about", "status", "progress"]);
+    items[0].resolution = Resolution::KeepNewerProgress;
+    assert_eq!(items[0].result().unwrap().episode, 10);
+    items[0].resolution = Resolution::TakeIncoming;
+    import::apply(&repository, &items).unwrap();
+
+    let show = frieren(&repository);
+    assert_eq!(
+        (show.about.as_str(), show.status, show.episode),
+        ("Beyond journey's end", Status::Completed, 4)
+    );
+    assert_eq!(show.episodes_count, 28);
+}
+
+#[test]
+fn apply_adds_new_shows_with_pictures() {
+    let repository = repository();
+    let mut picture = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(4, 6, image::Rgba([40, 40, 200, 255]))
+        .write_to(&mut picture, image::ImageFormat::Png)
+        .unwrap();
+    let items = import::preview(
+        &repository,
+        vec![IncomingShow {
+            show: ShowRecord {
+                id: 42,
+                title: "Dungeon Meshi".into(),
+                ..Default::default()
+            },
+            picture: Some(picture.into_inner()),
+            ..Default::default()
+        }],
+    )
+    .unwrap();
+
+    let summary = import::apply(&repository, &items).unwrap();
+    assert_eq!(summary.added, 1);
+    let shows = repository.load_shows().unwrap();
+    let show = shows.iter().find(|s| s.title == "Dungeon Meshi").unwrap();
+    assert_ne!(show.id, 42);
+    assert_eq!(show.season, 1);
+    assert!(repository.read_picture(show.id).unwrap().is_some());
+    assert_eq!(frieren(&repository).episode, 10);
+}
@@ -1,2 +1,9 @@
+mod config;
+mod database;
 mod datetime;
+mod formats;
+mod import;
+mod model;
 mod parsers;
+mod refresh;
+mod sites;
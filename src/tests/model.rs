@@ -0,0 +1,27 @@
+use crate::model::{ShowType, Status};
+
+#[test]
+fn status_database_values_round_trip() {
+    for status in [
+        Status::WatchLater,
+        Status::Watching,
+        Status::Completed,
+        Status::Dropped,
+    ] {
+        assert_eq!(Status::from_db(status.to_db()), status);
+    }
+    assert_eq!(Status::from_db(42), Status::WatchLater);
+}
+
+#[test]
+fn show_type_database_values_round_trip() {
+    for show_type in [
+        ShowType::Serial,
+        ShowType::Film,
+        ShowType::Cartoon,
+        ShowType::Anime,
+    ] {
+        assert_eq!(ShowType::from_db(show_type.to_db()), show_type);
+    }
+    assert_eq!(ShowType::from_db(-1), ShowType::Serial);
+}
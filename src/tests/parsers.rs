@@ -1,71 +1,227 @@
 use crate::{
+    http,
+    model::ShowType,
     parsers,
-    sites::{self, Sites},
+    sites::{self, imdb},
 };
+use regex::Regex;
+use std::path::PathBuf;
 
-fn check_site(link: &str, title: &str, alternative: &str, release_date: &str, about: &str) {
-    let site = sites::check_link_is_allowed_site(link);
-    let show = match site {
-        Sites::Imdb => parsers::imdb(link),
-        Sites::Unknown => panic!("Wrong site"),
-    };
-    assert_eq!(show.title.as_str(), title);
-    assert_eq!(show.alternative_title.as_str(), alternative);
-    assert_eq!(show.release_date.as_str(), release_date);
-    assert_eq!(show.about.as_str(), about);
+// The live tests read the pages from IMDb in Ukrainian, whatever language is set in the config,
+// and IMDb changes them over time. Run them with `cargo test -- --ignored`. The same pages are
+// saved by `save_imdb_pages`, so `saved_imdb_pages` checks the parser against them offline.
+
+struct Page {
+    /// The name of the saved page in `fixtures/imdb/saved`.
+    file: &'static str,
+    link: &'static str,
+    title: &'static str,
+    alternative: &'static str,
+    release_date: &'static str,
+    about: &'static str,
+    show_type: ShowType,
+}
+
+const THE_ORVILLE: Page = Page {
+    file: "the_orville",
+    link: "https://www.imdb.com/title/tt5691552/?ref_=nv_sr_srsg_0_tt_2_nm_0_q_%25D0%25BE%25D1%2580%25D0%25B2%25D1%2596%25D0%25BB",
+    title: "Орвіл",
+    alternative: "The Orville",
+    release_date: "2017–2022",
+    about: "Set 400 years in the future, the crew of the U.S.S. Orville continue their mission of exploration, navigating both the mysteries of the universe, and the complexities of their own interpersonal relationships.",
+    show_type: ShowType::Serial,
+};
+
+const MUSHOKU_TENSEI: Page = Page {
+    file: "mushoku_tensei",
+    link: "https://www.imdb.com/title/tt13293588/?ref_=nv_sr_srsg_0_tt_3_nm_0_q_%25D1%2580%25D0%25B5%25D1%2596%25D0%25BD%25D0%25BA",
+    title: "Реінкарнація безробітного: В інший світ на повному серйозі",
+    alternative: "Mushoku Tensei: Isekai Ittara Honki Dasu",
+    release_date: "2021– ",
+    about: "A 34-year-old underachiever gets run over by a bus, but his story isn't over. Reincarnated as an infant, he'll embark on an epic adventure.",
+    show_type: ShowType::Anime,
+};
+
+const YOUR_NAME: Page = Page {
+    file: "your_name",
+    link: "https://www.imdb.com/title/tt5311514/?ref_=nv_sr_srsg_0_tt_8_nm_0_q_%25D1%2596%25D0%25BC%27%25D1%258F",
+    title: "Твоє ім'я",
+    alternative: "Kimi no na wa.",
+    release_date: "2016",
+    about: "Two teenagers share a profound, magical connection upon discovering they are swapping bodies. Things manage to become even more complicated when the boy and girl decide to meet in person.",
+    show_type: ShowType::Film,
+};
+
+const IM_NOT_THERE: Page = Page {
+    file: "im_not_there",
+    link: "https://www.imdb.com/title/tt0368794/?ref_=nv_sr_srsg_3_tt_8_nm_0_q_i%27m",
+    title: "Мене там немає",
+    alternative: "I'm Not There",
+    release_date: "2007",
+    about: "Ruminations on the life of Bob Dylan, where six characters embody a different aspect of the musician's life and work.",
+    show_type: ShowType::Film,
+};
+
+const ONE_PIECE: Page = Page {
+    file: "one_piece",
+    link: "https://www.imdb.com/title/tt0388629/?ref_=nv_sr_srsg_0_tt_7_nm_1_q_one%2520pi",
+    title: "Ван Піс",
+    alternative: "One Piece: Wan pîsu",
+    release_date: "1999– ",
+    about: "Monkey D. Luffy sets off on an adventure with his pirate crew in hopes of finding the greatest treasure ever, known as the \"One Piece.\"",
+    show_type: ShowType::Anime,
+};
+
+const PAGES: [Page; 5] = [
+    THE_ORVILLE,
+    MUSHOKU_TENSEI,
+    YOUR_NAME,
+    IM_NOT_THERE,
+    ONE_PIECE,
+];
+
+fn check_page(page: &Page, content: &str) {
+    let show = imdb::parse(content, page.link).unwrap().show;
+    assert_eq!(show.title.as_str(), page.title);
+    assert_eq!(show.alternative_title.as_str(), page.alternative);
+    assert_eq!(show.release_date.as_str(), page.release_date);
+    assert_eq!(show.about.as_str(), page.about);
+    assert_eq!(show.show_type, page.show_type, "{}", page.file);
+    assert_eq!(show.link_to_show.as_str(), page.link);
+}
+
+fn check_site(page: &Page) {
+    assert!(sites::find(page.link).is_some_and(|site| site.name() == "IMDb"));
+    let show = imdb::fetch(page.link, Some("uk-UA")).unwrap().show;
+    assert_eq!(show.title.as_str(), page.title);
+    assert_eq!(show.alternative_title.as_str(), page.alternative);
+    assert_eq!(show.release_date.as_str(), page.release_date);
+    assert_eq!(show.about.as_str(), page.about);
 }
 
 #[test]
+#[ignore = "reads the live IMDb page"]
 fn imdb_the_orville() {
-    check_site(
-        "https://www.imdb.com/title/tt5691552/?ref_=nv_sr_srsg_0_tt_2_nm_0_q_%25D0%25BE%25D1%2580%25D0%25B2%25D1%2596%25D0%25BB",
-        "Орвіл",
-        "The Orville",
-        "2017–2022",
-        "Set 400 years in the future, the crew of the U.S.S. Orville continue their mission of exploration, navigating both the mysteries of the universe, and the complexities of their own interpersonal relationships."
-    );
+    check_site(&THE_ORVILLE);
 }
 
 #[test]
+#[ignore = "reads the live IMDb page"]
 fn imdb_mushoku_tensei() {
-    check_site(
-        "https://www.imdb.com/title/tt13293588/?ref_=nv_sr_srsg_0_tt_3_nm_0_q_%25D1%2580%25D0%25B5%25D1%2596%25D0%25BD%25D0%25BA",
-        "Реінкарнація безробітного: В інший світ на повному серйозі",
-        "Mushoku Tensei: Isekai Ittara Honki Dasu",
-        "2021– ",
-        "A 34-year-old underachiever gets run over by a bus, but his story isn't over. Reincarnated as an infant, he'll embark on an epic adventure."
-    );
+    check_site(&MUSHOKU_TENSEI);
 }
 
 #[test]
+#[ignore = "reads the live IMDb page"]
 fn imdb_your_name() {
-    check_site(
-        "https://www.imdb.com/title/tt5311514/?ref_=nv_sr_srsg_0_tt_8_nm_0_q_%25D1%2596%25D0%25BC%27%25D1%258F",
-        "Твоє ім'я",
-        "Kimi no na wa.",
-        "2016",
-        "Two teenagers share a profound, magical connection upon discovering they are swapping bodies. Things manage to become even more complicated when the boy and girl decide to meet in person."
-    );
+    check_site(&YOUR_NAME);
 }
 
 #[test]
+#[ignore = "reads the live IMDb page"]
 fn imdb_im_not_there() {
-    check_site(
-        "https://www.imdb.com/title/tt0368794/?ref_=nv_sr_srsg_3_tt_8_nm_0_q_i%27m",
-        "Мене там немає",
-        "I'm Not There",
-        "2007",
-        "Ruminations on the life of Bob Dylan, where six characters embody a different aspect of the musician's life and work."
-    );
+    check_site(&IM_NOT_THERE);
 }
 
 #[test]
+#[ignore = "reads the live IMDb page"]
 fn imdb_yoru_no_kurage() {
-    check_site(
-        "https://www.imdb.com/title/tt0388629/?ref_=nv_sr_srsg_0_tt_7_nm_1_q_one%2520pi",
-        "Ван Піс",
-        "One Piece: Wan pîsu",
-        "1999– ",
-        "Monkey D. Luffy sets off on an adventure with his pirate crew in hopes of finding the greatest treasure ever, known as the \"One Piece.\""
+    check_site(&ONE_PIECE);
+}
+
+fn saved_page(file: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/tests/fixtures/imdb/saved")
+        .join(format!("{}.html", file))
+}
+
+/// Leaves out the scripts, styles and other assets the parser never reads, keeping the markup
+/// and the scripts holding the data of the page.
+fn trim_page(page: &str) -> String {
+    let scripts = Regex::new(r"(?s)<script\b[^>]*>.*?</script>").unwrap();
+    let page = scripts.replace_all(page, |c: &regex::Captures| {
+        let script = &c[0];
+        let open = &script[..script.find('>').unwrap_or_default()];
+        match open.contains(r#"id="__NEXT_DATA__""#) || open.contains("application/ld+json") {
+            true => script.to_owned(),
+            false => String::new(),
+        }
+    });
+    let assets = Regex::new(
+        r"(?s)<style\b[^>]*>.*?</style>|<noscript\b[^>]*>.*?</noscript>|<svg\b[^>]*>.*?</svg>|<link\b[^>]*>|<!--.*?-->",
+    )
+    .unwrap();
+    assets.replace_all(&page, "").into_owned()
+}
+
+/// Saves the pages of the live tests for `saved_imdb_pages`. Run it with
+/// `cargo test save_imdb_pages -- --ignored` and commit the pages it writes.
+#[test]
+#[ignore = "reads the live IMDb pages and overwrites the saved ones"]
+fn save_imdb_pages() {
+    for page in &PAGES {
+        let content = http::get(page.link, &http::language_headers(Some("uk-UA"))).unwrap();
+        let path = saved_page(page.file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, trim_page(&content)).unwrap();
+        check_page(page, &std::fs::read_to_string(&path).unwrap());
+    }
+}
+
+#[test]
+#[ignore = "the saved pages are not committed yet, save them with save_imdb_pages"]
+fn saved_imdb_pages() {
+    for page in &PAGES {
+        let path = saved_page(page.file);
+        let content = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        check_page(page, &content);
+    }
+}
+
+#[test]
+fn pages_are_trimmed_to_what_the_parser_reads() {
+    let page = r#"<html><head><link rel="stylesheet" href="a.css"><style>h1 { color: red }</style>
+<script src="app.js"></script><script>window.ads = [];</script>
+<script type="application/ld+json">{"@type":"Movie","name":"I'm Not There"}</script>
+</head><body><!-- ad --><svg><path d="M0"/></svg><h1><span class="hero__primary-text">Мене там немає</span></h1>
+<script id="__NEXT_DATA__" type="application/json">{"props":{}}</script></body></html>"#;
+    assert_eq!(
+        trim_page(page),
+        r#"<html><head>
+
+<script type="application/ld+json">{"@type":"Movie","name":"I'm Not There"}</script>
+</head><body><h1><span class="hero__primary-text">Мене там немає</span></h1>
+<script id="__NEXT_DATA__" type="application/json">{"props":{}}</script></body></html>"#
+    );
+}
+
+#[test]
+fn html_is_read_as_text() {
+    assert_eq!(
+        parsers::replace_html_entities("Tom &amp; Jerry&#39;s &quot;show&quot;"),
+        "Tom & Jerry's \"show\""
+    );
+    assert_eq!(
+        parsers::html_to_text("<p>First line.<br>\nSecond <i>line</i>.</p> ").unwrap(),
+        "First line.\nSecond line."
+    );
+}
+
+#[test]
+fn links_are_matched_to_sites() {
+    let site = |link| sites::find(link).map(|site| site.name());
+    assert_eq!(site("https://www.imdb.com/title/tt5691552/"), Some("IMDb"));
+    assert_eq!(site("http://m.IMDb.com/title/tt5691552/"), Some("IMDb"));
+    assert_eq!(site("https://imdb.com:443/title/tt5691552"), Some("IMDb"));
+    assert_eq!(
+        site("https://notimdb.com/title/tt5691552/"),
+        Some("Web page")
+    );
+    assert_eq!(
+        site("https://example.com/?next=https://www.imdb.com/"),
+        Some("Web page")
     );
+    assert_eq!(site("www.imdb.com/title/tt5691552/"), None);
+    assert!(!sites::check_link_is_importable(""));
 }
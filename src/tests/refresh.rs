@@ -0,0 +1,229 @@
+use crate::{
+    database::Repository,
+    http,
+    model::{Schedule, ShowRecord, Status},
+    refresh::{self, FieldChange},
+    sites::{ImportError, ImportedShow},
+};
+
+fn repository() -> Repository {
+    Repository::from_connection(sqlite::open(":memory:").unwrap()).unwrap()
+}
+
+fn png() -> Vec<u8> {
+    let mut content = std::io::Cursor::new(Vec::new());
+    image::RgbaImage::from_pixel(4, 6, image::Rgba([200, 40, 40, 255]))
+        .write_to(&mut content, image::ImageFormat::Png)
+        .unwrap();
+    content.into_inner()
+}
+
+fn imported() -> ShowRecord {
+    ShowRecord {
+        title: "Mushoku Tensei".into(),
+        alternative_title: "Mushoku Tensei: Isekai Ittara Honki Dasu".into(),
+        release_date: "2021– ".into(),
+        about: "A 34-year-old underachiever gets run over by a bus.".into(),
+        link_to_show: "https://www.imdb.com/title/tt13293588/".into(),
+        link_to_picture: "https://example.com/mushoku_tensei.jpg".into(),
+        episodes_count: 12,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn changes_keep_edited_fields() {
+    let local = imported();
+    let fetched = ShowRecord {
+        release_date: "2021–2024".into(),
+        about: "A 34-year-old NEET is reincarnated.".into(),
+        alternative_title: String::new(),
+        episodes_count: 0,
+        schedule: Schedule {
+            release_time: "2024-07-08 00:00".into(),
+            episodes_per_weekday: [1, 0, 0, 0, 0, 0, 0],
+        },
+        ..imported()
+    };
+    let edited = vec!["about".to_owned()];
+
+    let changes = refresh::compare(&local, None, &fetched, &edited);
+    let fields = changes.iter().map(|c| c.field).collect::<Vec<_>>();
+    assert_eq!(fields, ["release date", "about", "schedule"]);
+    assert_eq!(
+        changes[0],
+        FieldChange {
+            field: "release date",
+            local: "2021– ".into(),
+            fetched: "2021–2024".into(),
+            edited: false,
+        }
+    );
+    assert!(changes[1].edited);
+    assert_eq!(
+        changes[1].to_string(),
+        "about: A 34-year-old underachiever gets run ove… → A 34-year-old NEET is reincarnated. \
+         (edited by hand, kept)"
+    );
+    assert_eq!(
+        changes[2].to_string(),
+        "schedule: none → Mon from 2024-07-08 00:00"
+    );
+
+    let picture = refresh::compare(&local, Some(""), &fetched, &edited);
+    assert_eq!(picture.last().unwrap().field, "picture");
+}
+
+#[test]
+fn entered_shows_remember_edited_fields() {
+    let repository = repository();
+    let imported = imported();
+    let show = ShowRecord {
+        about: "Rudeus grows up in another world.".into(),
+        link_to_picture: String::new(),
+        ..imported.clone()
+    };
+    let id = refresh::save_entered_show(&repository, &show, Some(&imported)).unwrap();
+    assert_eq!(repository.edited_fields(id).unwrap(), ["about"]);
+
+    let edited = ShowRecord {
+        id,
+        release_date: "2021".into(),
+        ..show
+    };
+    refresh::save_entered_show(&repository, &edited, None).unwrap();
+    assert_eq!(
+        repository.edited_fields(id).unwrap(),
+        ["about", "release date"]
+    );
+
+    let typed = ShowRecord {
+        title: "Perfect Blue".into(),
+        release_date: "1997".into(),
+        ..Default::default()
+    };
+    let id = refresh::save_entered_show(&repository, &typed, Some(&imported)).unwrap();
+    assert_eq!(repository.edited_fields(id).unwrap(), ["release date"]);
+}
+
+#[test]
+fn refresh_updates_watching_shows() {
+    let repository = repository();
+    let imported = imported();
+    let show = ShowRecord {
+        about: "Rudeus grows up in another world.".into(),
+        link_to_picture: String::new(),
+        status: Status::Watching,
+        ..imported.clone()
+    };
+    let id = refresh::save_entered_show(&repository, &show, Some(&imported)).unwrap();
+    let gone = ShowRecord {
+        title: "Gone".into(),
+        link_to_show: "https://www.tvmaze.com/shows/0/gone".into(),
+        status: Status::Watching,
+        ..Default::default()
+    };
+    repository.save_show(&gone).unwrap();
+    repository
+        .save_show(&ShowRecord {
+            title: "Planned".into(),
+            link_to_show: imported.link_to_show.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+
+    let fetch = |link: &str| match link {
+        "https://www.imdb.com/title/tt13293588/" => Ok(ImportedShow::from(ShowRecord {
+            release_date: "2021–2024".into(),
+            about: "A 34-year-old NEET is reincarnated.".into(),
+            link_to_picture: "https://example.com/mushoku_tensei_2.jpg".into(),
+            ..imported.clone()
+        })),
+        _ => Err(ImportError::Status {
+            url: link.to_owned(),
+            status: 404,
+        }),
+    };
+    let shows = refresh::watching(&repository).unwrap();
+    let items = refresh::preview(&repository, &shows, fetch).unwrap();
+    assert_eq!(items.len(), 2);
+    let item = items.iter().find(|item| item.local.id == id).unwrap();
+    let fields = item.changes.iter().map(|c| c.field).collect::<Vec<_>>();
+    assert_eq!(fields, ["release date", "about", "picture"]);
+    assert!(items.iter().any(|item| item.fetched.is_err()));
+
+    let updated = refresh::apply(&repository, &items, |_| Ok(png())).unwrap();
+    assert_eq!(updated, 1);
+    let refreshed = repository.load_show(id).unwrap();
+    assert_eq!(refreshed.release_date, "2021–2024");
+    assert_eq!(refreshed.about, "Rudeus grows up in another world.");
+    assert!(repository.read_picture(id).unwrap().is_some());
+    assert_eq!(
+        repository.picture_url(id).unwrap().as_deref(),
+        Some("https://example.com/mushoku_tensei_2.jpg")
+    );
+
+    let items = refresh::preview(&repository, &[refreshed], fetch).unwrap();
+    assert_eq!(items[0].changes.len(), 1);
+    assert!(items[0].result().is_none());
+}
+
+#[test]
+fn replaced_pictures_are_kept() {
+    let repository = repository();
+    let link_to_picture = format!(
+        "https://example.com/watchlist-test-{}-poster.png",
+        std::process::id()
+    );
+    let downloaded = http::download_path(&link_to_picture);
+    std::fs::create_dir_all(downloaded.parent().unwrap()).unwrap();
+    std::fs::write(&downloaded, png()).unwrap();
+    let chosen =
+        std::env::temp_dir().join(format!("watchlist-test-{}-chosen.png", std::process::id()));
+    std::fs::write(&chosen, png()).unwrap();
+
+    let imported = ShowRecord {
+        link_to_picture,
+        ..imported()
+    };
+    let show = |picture: &std::path::Path| ShowRecord {
+        link_to_picture: picture.to_string_lossy().into_owned(),
+        ..imported.clone()
+    };
+    let kept =
+        refresh::save_entered_show(&repository, &show(&downloaded), Some(&imported)).unwrap();
+    // Titles are unique, so the second show is a sequel.
+    let sequel = ShowRecord {
+        title: "Mushoku Tensei II".into(),
+        ..show(&chosen)
+    };
+    let replaced = refresh::save_entered_show(&repository, &sequel, Some(&imported)).unwrap();
+    _ = std::fs::remove_file(&downloaded);
+    _ = std::fs::remove_file(&chosen);
+
+    assert_eq!(
+        repository.picture_url(kept).unwrap(),
+        Some(imported.link_to_picture.clone())
+    );
+    assert!(repository.edited_fields(kept).unwrap().is_empty());
+    assert_eq!(repository.picture_url(replaced).unwrap(), None);
+    assert_eq!(repository.edited_fields(replaced).unwrap(), ["picture"]);
+
+    let fetch = |_: &str| {
+        Ok(ImportedShow::from(ShowRecord {
+            link_to_picture: "https://example.com/mushoku_tensei_2.jpg".into(),
+            ..imported.clone()
+        }))
+    };
+    let shows = [
+        repository.load_show(kept).unwrap(),
+        repository.load_show(replaced).unwrap(),
+    ];
+    let items = refresh::preview(&repository, &shows, fetch).unwrap();
+    assert_eq!(
+        items[0].picture(),
+        Some("https://example.com/mushoku_tensei_2.jpg")
+    );
+    assert_eq!(items[1].picture(), None);
+    assert!(items[1].result().is_none());
+}
@@ -0,0 +1,575 @@
+use crate::{
+    config::Config,
+    http,
+    model::{Schedule, ShowRecord, ShowType},
+    sites::{
+        self, anilist, generic, imdb, myanimelist,
+        tmdb::{self, Media},
+        tvmaze, ImportError, SearchResult,
+    },
+};
+use chrono::{DateTime, Datelike, Local};
+
+#[test]
+fn tmdb_links() {
+    assert_eq!(
+        tmdb::media("https://www.themoviedb.org/tv/209867-sousou-no-frieren?language=uk"),
+        Some((Media::Tv, 209867))
+    );
+    assert_eq!(
+        tmdb::media("https://themoviedb.org/movie/372058"),
+        Some((Media::Movie, 372058))
+    );
+    assert_eq!(
+        tmdb::media("https://www.themoviedb.org/person/1-george-lucas"),
+        None
+    );
+    assert_eq!(tmdb::media("https://www.themoviedb.org/tv/"), None);
+}
+
+#[test]
+fn tmdb_anime() {
+    let link = "https://www.themoviedb.org/tv/209867";
+    let content = include_str!("fixtures/tmdb/tv_frieren.json");
+    let imported = tmdb::parse(Media::Tv, content, link).unwrap();
+    assert_eq!(imported.seasons, Some(2));
+    assert_eq!(
+        imported.show,
+        ShowRecord {
+            title: "Frieren: Beyond Journey's End".into(),
+            alternative_title: "葬送のフリーレン".into(),
+            release_date: "2023".into(),
+            about: "After the party of heroes defeated the Demon King, they restored peace to the \
+                    land and returned to lives of solitude."
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture: "https://image.tmdb.org/t/p/w500/dqZENchTd7lp5zht7BdlqM7RBhD.jpg"
+                .into(),
+            show_type: ShowType::Anime,
+            episodes_count: 10,
+            schedule: Schedule {
+                release_time: "2026-01-16 00:00".into(),
+                episodes_per_weekday: [0, 0, 0, 0, 1, 0, 0],
+            },
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn tmdb_cartoon_and_film() {
+    let content = include_str!("fixtures/tmdb/tv_arcane.json");
+    let imported = tmdb::parse(Media::Tv, content, "").unwrap();
+    assert_eq!(imported.seasons, Some(2));
+    let show = imported.show;
+    assert_eq!(show.show_type, ShowType::Cartoon);
+    assert_eq!(show.alternative_title, "Arcane");
+    assert_eq!(show.release_date, "2021–2024");
+    assert_eq!(show.schedule.episodes_per_weekday, [0, 0, 0, 0, 0, 1, 0]);
+
+    let content = include_str!("fixtures/tmdb/movie_your_name.json");
+    let imported = tmdb::parse(Media::Movie, content, "").unwrap();
+    assert_eq!(imported.seasons, None);
+    let show = imported.show;
+    assert_eq!(
+        (show.title.as_str(), show.alternative_title.as_str()),
+        ("Your Name.", "君の名は。")
+    );
+    assert_eq!(show.release_date, "2016");
+    assert_eq!((show.show_type, show.episodes_count), (ShowType::Film, 1));
+    assert_eq!(show.schedule, Schedule::default());
+
+    assert!(tmdb::parse(Media::Movie, "{\"status_code\": 34}", "").is_err());
+}
+
+#[test]
+fn tvmaze_links() {
+    assert_eq!(
+        tvmaze::show_id("https://www.tvmaze.com/shows/70145/kaiju-no-8"),
+        Some(70145)
+    );
+    assert_eq!(
+        tvmaze::show_id("https://www.tvmaze.com/episodes/2800001/kaiju-no-8-1x01"),
+        None
+    );
+    assert_eq!(tvmaze::show_id("https://www.tvmaze.com/shows/"), None);
+}
+
+#[test]
+fn tvmaze_fills_the_schedule() {
+    let link = "https://www.tvmaze.com/shows/70145/kaiju-no-8";
+    let content = include_str!("fixtures/tvmaze/kaiju_no_8.json");
+    let show = tvmaze::parse(content, link).unwrap().show;
+
+    // Episodes air on Saturday 23:00 in Tokyo, which is another time and maybe day here.
+    let premiere = DateTime::parse_from_rfc3339("2024-04-13T14:00:00+00:00")
+        .unwrap()
+        .with_timezone(&Local);
+    let mut episodes_per_weekday = [0; 7];
+    episodes_per_weekday[premiere.weekday().num_days_from_monday() as usize] = 1;
+    assert_eq!(
+        show,
+        ShowRecord {
+            title: "Kaiju No. 8".into(),
+            alternative_title: "Kaiju No. 8".into(),
+            release_date: "2024".into(),
+            about: "In a Japan riddled with monsters, Kafka Hibino works cleaning up after the \
+                    Defense Force's battles & dreams of joining it."
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture:
+                "https://static.tvmaze.com/uploads/images/original_untouched/507/1268966.jpg".into(),
+            show_type: ShowType::Anime,
+            episodes_count: 12,
+            schedule: Schedule {
+                release_time: premiere.format("%Y-%m-%d %H:%M").to_string(),
+                episodes_per_weekday,
+            },
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn tvmaze_schedule_without_episodes() {
+    let content = r#"{
+        "name": "The Orville",
+        "type": "Scripted",
+        "language": "English",
+        "premiered": "2017-09-10",
+        "ended": "2022-08-04",
+        "schedule": { "time": "21:00", "days": ["Thursday"] },
+        "network": { "name": "FOX", "country": { "timezone": "America/New_York" } },
+        "_embedded": { "episodes": [] }
+    }"#;
+    let show = tvmaze::parse(content, "").unwrap().show;
+    assert_eq!(show.show_type, ShowType::Serial);
+    assert_eq!(show.release_date, "2017–2022");
+    // The premiere is on Sunday in New York, and Thursday stays four days after it wherever
+    // that falls in local time.
+    let premiere = weekly_from("2017-09-10T21:00:00-04:00");
+    let sunday = premiere.episodes_per_weekday.iter().position(|&e| e == 1);
+    let mut episodes_per_weekday = [0; 7];
+    episodes_per_weekday[(sunday.unwrap() + 4) % 7] = 1;
+    assert_eq!(
+        show.schedule,
+        Schedule {
+            release_time: premiere.release_time,
+            episodes_per_weekday,
+        }
+    );
+
+    let later_season = content.replace(
+        "\"episodes\": []",
+        r#""episodes": [
+            { "season": 1, "number": 1, "airdate": "2017-09-10" },
+            { "season": 3, "number": 2, "airdate": "2022-06-09" },
+            { "season": 3, "number": 1, "airdate": "2022-06-02" }
+        ]"#,
+    );
+    let show = tvmaze::parse(&later_season, "").unwrap().show;
+    assert_eq!(show.episodes_count, 2);
+    let premiere = weekly_from("2022-06-02T21:00:00-04:00");
+    assert_eq!(show.schedule.release_time, premiere.release_time);
+    assert_eq!(
+        show.schedule.episodes_per_weekday,
+        premiere.episodes_per_weekday
+    );
+
+    let worldwide = content.replace("{ \"timezone\": \"America/New_York\" }", "null");
+    let show = tvmaze::parse(&worldwide, "").unwrap().show;
+    assert_eq!(show.schedule, Schedule::default());
+}
+
+fn weekly_from(airstamp: &str) -> Schedule {
+    let first = DateTime::parse_from_rfc3339(airstamp)
+        .unwrap()
+        .with_timezone(&Local);
+    let mut episodes_per_weekday = [0; 7];
+    episodes_per_weekday[first.weekday().num_days_from_monday() as usize] = 1;
+    Schedule {
+        release_time: first.format("%Y-%m-%d %H:%M").to_string(),
+        episodes_per_weekday,
+    }
+}
+
+#[test]
+fn anime_links() {
+    let site = |link| sites::find(link).map(|site| site.name());
+    assert_eq!(
+        site("https://anilist.co/anime/178754/Kaijuu-8gou/"),
+        Some("AniList")
+    );
+    assert_eq!(anilist::anime_id("https://anilist.co/manga/119257/"), None);
+    assert_eq!(
+        site("https://myanimelist.net/anime/52991/Sousou_no_Frieren"),
+        Some("MyAnimeList")
+    );
+    assert_eq!(
+        myanimelist::anime_id("https://myanimelist.net/anime.php?id=52991"),
+        None
+    );
+    assert_eq!(
+        anilist::anime_id("https://anilist.co/anime/178754"),
+        Some(178754)
+    );
+}
+
+#[test]
+fn anilist_anime() {
+    let link = "https://anilist.co/anime/178754/Kaijuu-8gou/";
+    let content = include_str!("fixtures/anilist/kaijuu_8_gou.json");
+    let show = anilist::parse(content, link, None).unwrap().show;
+    assert_eq!(
+        show,
+        ShowRecord {
+            title: "Kaijuu 8-gou".into(),
+            alternative_title: "Kaiju No. 8".into(),
+            release_date: "2024".into(),
+            about: "Kafka Hibino, a 32-year-old man, works cleaning up after kaiju attacks.\n\n\
+                    (Source: Crunchyroll)"
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture:
+                "https://s4.anilist.co/file/anilistcdn/media/anime/cover/large/bx178754-4KvTJ4Jl6ZeW.jpg"
+                    .into(),
+            show_type: ShowType::Anime,
+            episodes_count: 12,
+            // Two weeks before the third episode.
+            schedule: weekly_from("2024-04-13T14:00:00+00:00"),
+            ..Default::default()
+        }
+    );
+
+    let missing =
+        r#"{"errors": [{"message": "Not Found.", "status": 404}], "data": {"Media": null}}"#;
+    assert!(anilist::parse(missing, link, None).is_err());
+
+    let movie = content.replace("\"format\": \"TV\"", "\"format\": \"MOVIE\"");
+    let show = anilist::parse(&movie, link, None).unwrap().show;
+    assert_eq!(show.show_type, ShowType::Film);
+}
+
+#[test]
+fn myanimelist_anime() {
+    let link = "https://myanimelist.net/anime/52991/Sousou_no_Frieren";
+    let content = include_str!("fixtures/myanimelist/sousou_no_frieren.json");
+    let show = myanimelist::parse(content, link, None).unwrap().show;
+    assert_eq!(
+        show,
+        ShowRecord {
+            title: "Sousou no Frieren".into(),
+            alternative_title: "Frieren: Beyond Journey's End".into(),
+            release_date: "2023–2024".into(),
+            about: "During their decade-long quest to defeat the Demon King, the members of the \
+                    hero's party forge bonds through adventures and battles."
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture: "https://cdn.myanimelist.net/images/anime/1015/138006l.jpg".into(),
+            show_type: ShowType::Anime,
+            episodes_count: 28,
+            // Fridays at 23:00 in Tokyo.
+            schedule: weekly_from("2023-09-29T14:00:00+00:00"),
+            ..Default::default()
+        }
+    );
+
+    let movie = content.replace("\"type\": \"TV\"", "\"type\": \"Movie\"");
+    let show = myanimelist::parse(&movie, link, None).unwrap().show;
+    assert_eq!(show.show_type, ShowType::Film);
+}
+
+#[test]
+fn anime_titles_in_language() {
+    let content = include_str!("fixtures/anilist/kaijuu_8_gou.json");
+    let link = "https://anilist.co/anime/178754/Kaijuu-8gou/";
+    let titles = |language| {
+        let show = anilist::parse(content, link, language).unwrap().show;
+        (show.title, show.alternative_title)
+    };
+    assert_eq!(
+        titles(Some("en-US")),
+        ("Kaiju No. 8".into(), "Kaijuu 8-gou".into())
+    );
+    assert_eq!(
+        titles(Some("ja")),
+        ("怪獣８号".into(), "Kaijuu 8-gou".into())
+    );
+    assert_eq!(
+        titles(Some("uk-UA")),
+        ("Kaijuu 8-gou".into(), "Kaiju No. 8".into())
+    );
+
+    let content = include_str!("fixtures/myanimelist/sousou_no_frieren.json");
+    let link = "https://myanimelist.net/anime/52991/Sousou_no_Frieren";
+    let show = myanimelist::parse(content, link, Some("en_GB"))
+        .unwrap()
+        .show;
+    assert_eq!(show.title, "Frieren: Beyond Journey's End");
+    assert_eq!(show.alternative_title, "Sousou no Frieren");
+
+    assert_eq!(
+        sites::anime_titles("Perfect Blue", None, None, Some("en")),
+        ("Perfect Blue".into(), "Perfect Blue".into())
+    );
+}
+
+fn page(content: &str) -> webpage::HTML {
+    webpage::HTML::from_string(content.to_owned(), None).unwrap()
+}
+
+#[test]
+fn generic_page_with_json_ld() {
+    let link = "https://streaming.example.com/series/the-orville";
+    let html = page(include_str!("fixtures/pages/json_ld_series.html"));
+    let show = generic::parse(&html, link).unwrap().show;
+    assert_eq!(
+        show,
+        ShowRecord {
+            title: "The Orville".into(),
+            alternative_title: "The Orville: New Horizons".into(),
+            release_date: "2017".into(),
+            about: "Set 400 years in the future, the crew of the U.S.S. Orville continue their \
+                    mission of exploration."
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture: "https://example.com/images/orville.jpg".into(),
+            show_type: ShowType::Serial,
+            episodes_count: 36,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn generic_page_with_opengraph() {
+    let link = "https://wiki.example.org/wiki/Perfect_Blue";
+    let html = page(include_str!("fixtures/pages/opengraph_movie.html"));
+    let show = generic::parse(&html, link).unwrap().show;
+    assert_eq!(
+        (show.title.as_str(), show.alternative_title.as_str()),
+        ("Perfect Blue", "Perfect Blue")
+    );
+    assert!(show.about.starts_with("A pop singer gives up her career"));
+    assert_eq!(
+        show.link_to_picture,
+        "https://wiki.example.org/images/perfect_blue.jpg"
+    );
+    assert_eq!(show.show_type, ShowType::Film);
+
+    assert!(generic::parse(&page("<html><body></body></html>"), link).is_err());
+}
+
+// The IMDb pages below are excerpts holding only one source of data each, to check how the
+// parser falls back from one to the next. Whole pages are checked by `saved_imdb_pages`.
+
+#[test]
+fn imdb_page_with_next_data() {
+    let link = "https://www.imdb.com/title/tt5691552/";
+    let imported = imdb::parse(include_str!("fixtures/imdb/the_orville.html"), link).unwrap();
+    assert_eq!(imported.show.title, "Орвіл");
+    assert_eq!(imported.show.alternative_title, "The Orville");
+    assert_eq!(imported.show.release_date, "2017–2022");
+    assert!(imported
+        .show
+        .about
+        .ends_with("their own interpersonal relationships."));
+    assert_eq!(
+        imported.show.link_to_picture,
+        "https://m.media-amazon.com/images/M/MV5BOTE5Mzc1MTk@._V1_.jpg"
+    );
+    assert_eq!(imported.show.link_to_show, link);
+    assert_eq!(imported.show.show_type, ShowType::Serial);
+    assert_eq!(imported.genres, ["Comedy", "Adventure", "Drama"]);
+    assert_eq!(imported.runtime, Some(44));
+    assert_eq!(imported.rating, Some(7.9));
+    assert_eq!(imported.seasons, Some(3));
+    assert_eq!(
+        imported.details(),
+        "Comedy, Adventure, Drama · 44 min · 7.9/10 · 3 seasons"
+    );
+}
+
+#[test]
+fn imdb_page_with_json_ld() {
+    let link = "https://www.imdb.com/title/tt5311514/";
+    let imported = imdb::parse(include_str!("fixtures/imdb/your_name_json_ld.html"), link).unwrap();
+    assert_eq!(imported.show.title, "Your Name.");
+    assert_eq!(imported.show.alternative_title, "Kimi no na wa.");
+    assert_eq!(imported.show.release_date, "2016");
+    assert_eq!(imported.show.show_type, ShowType::Film);
+    assert_eq!(imported.genres, ["Animation", "Drama", "Fantasy"]);
+    assert_eq!(imported.runtime, Some(106));
+    assert_eq!(imported.rating, Some(8.4));
+    assert_eq!(imported.seasons, None);
+    assert_eq!(
+        imported.details(),
+        "Animation, Drama, Fantasy · 106 min · 8.4/10"
+    );
+}
+
+#[test]
+fn imdb_page_markup() {
+    let link = "https://www.imdb.com/title/tt0388629/";
+    let imported = imdb::parse(include_str!("fixtures/imdb/one_piece_markup.html"), link).unwrap();
+    assert_eq!(
+        imported.show,
+        ShowRecord {
+            title: "One Piece".into(),
+            alternative_title: "One Piece: Wan pîsu".into(),
+            release_date: "1999– ".into(),
+            about: "Monkey D. Luffy sets off on an adventure with his pirate crew in hopes of \
+                    finding the greatest treasure ever, known as the \"One Piece.\""
+                .into(),
+            link_to_show: link.into(),
+            link_to_picture: "https://m.media-amazon.com/images/M/MV5BODcwNWE3OTM@._V1_.jpg".into(),
+            ..Default::default()
+        }
+    );
+    assert!(imported.genres.is_empty());
+
+    let empty = imdb::parse("<html><body></body></html>", link).unwrap();
+    assert_eq!(empty.show.title, "");
+}
+
+#[test]
+fn import_errors() {
+    assert_eq!(
+        sites::fetch("ftp://example.com/show", &Config::default()),
+        Err(ImportError::Unsupported("ftp://example.com/show".into()))
+    );
+
+    let status = anyhow::Error::new(http::StatusError {
+        url: "https://api.tvmaze.com/shows/0".into(),
+        status: 404,
+    });
+    assert_eq!(
+        ImportError::from(status.context("Failed to fetch the show")),
+        ImportError::Status {
+            url: "https://api.tvmaze.com/shows/0".into(),
+            status: 404
+        }
+    );
+    assert!(matches!(
+        ImportError::from(anyhow::anyhow!("expected value")),
+        ImportError::Parse(_)
+    ));
+
+    let show = ShowRecord {
+        link_to_picture: "https://example.com/poster.jpg".into(),
+        episodes_count: 12,
+        ..Default::default()
+    };
+    let (found, missing) = sites::fields(&show);
+    assert_eq!(found, ["picture", "episodes count"]);
+    assert_eq!(
+        missing,
+        ["title", "release date", "description", "schedule"]
+    );
+    assert_eq!(
+        ImportError::MissingFields { found, missing }.to_string(),
+        "Found no title, release date, description, schedule on the page, only picture, \
+         episodes count"
+    );
+}
+
+fn titles(results: &[SearchResult]) -> Vec<&str> {
+    results.iter().map(|r| r.title.as_str()).collect()
+}
+
+#[test]
+fn show_site_search() {
+    let results = tmdb::parse_search(include_str!("fixtures/tmdb/search_your_name.json")).unwrap();
+    assert_eq!(
+        titles(&results),
+        ["Your Name.", "Your Name Engraved Herein"]
+    );
+    assert_eq!(
+        results[0],
+        SearchResult {
+            title: "Your Name.".into(),
+            year: "2016".into(),
+            show_type: ShowType::Film,
+            link: "https://www.themoviedb.org/movie/372058".into(),
+            picture: "https://image.tmdb.org/t/p/w154/q719jXXEzOoYaps6babgKnONONX.jpg".into(),
+            ..Default::default()
+        }
+    );
+    assert_eq!(results[1].show_type, ShowType::Serial);
+    assert_eq!(results[1].picture, "");
+    assert!(tmdb::media(&results[1].link).is_some());
+
+    let results = tvmaze::parse_search(include_str!("fixtures/tvmaze/search_kaiju.json")).unwrap();
+    assert_eq!(titles(&results), ["Kaiju No. 8", "Kaiju Girls"]);
+    assert_eq!(
+        results[0],
+        SearchResult {
+            title: "Kaiju No. 8".into(),
+            year: "2024".into(),
+            show_type: ShowType::Anime,
+            link: "https://www.tvmaze.com/shows/70145/kaiju-no-8".into(),
+            picture: "https://static.tvmaze.com/uploads/images/medium_portrait/510/1276427.jpg"
+                .into(),
+            ..Default::default()
+        }
+    );
+    assert_eq!(tvmaze::show_id(&results[1].link), Some(41245));
+
+    let results =
+        imdb::parse_search(include_str!("fixtures/imdb/suggestion_the_orville.json")).unwrap();
+    assert_eq!(
+        titles(&results),
+        [
+            "The Orville",
+            "The Orville: New Horizons - Behind the Scenes"
+        ]
+    );
+    assert_eq!(results[0].year, "2017-2022");
+    assert_eq!(results[0].show_type, ShowType::Serial);
+    assert_eq!(results[0].link, "https://www.imdb.com/title/tt5691552/");
+    assert!(results[0]
+        .picture
+        .starts_with("https://m.media-amazon.com/"));
+    assert_eq!(results[1].year, "2022");
+    assert_eq!(results[1].show_type, ShowType::Film);
+    assert!(imdb::parse_search(r#"{"v": 1}"#).unwrap().is_empty());
+}
+
+#[test]
+fn anime_site_search() {
+    let results =
+        anilist::parse_search(include_str!("fixtures/anilist/search_kaijuu.json")).unwrap();
+    assert_eq!(
+        titles(&results),
+        ["Kaijuu 8-gou", "Kaijuu 8-gou: Hoshina no Kyuujitsu"]
+    );
+    assert_eq!(
+        results[0],
+        SearchResult {
+            title: "Kaijuu 8-gou".into(),
+            year: "2024".into(),
+            show_type: ShowType::Anime,
+            link: "https://anilist.co/anime/178754".into(),
+            picture: "https://s4.anilist.co/file/anilistcdn/media/anime/cover/small/bx178754-sJ6yUu1KWyHV.jpg"
+                .into(),
+            ..Default::default()
+        }
+    );
+    assert_eq!(anilist::anime_id(&results[1].link), Some(185732));
+
+    let results =
+        myanimelist::parse_search(include_str!("fixtures/myanimelist/search_frieren.json"))
+            .unwrap();
+    assert_eq!(
+        titles(&results),
+        ["Sousou no Frieren", "Sousou no Frieren: ●● no Mahou"]
+    );
+    assert_eq!(results[0].year, "2023");
+    assert_eq!(
+        results[0].picture,
+        "https://cdn.myanimelist.net/images/anime/1015/138006.jpg"
+    );
+    assert_eq!(myanimelist::anime_id(&results[0].link), Some(52991));
+    assert_eq!(results[1].year, "");
+    assert_eq!(results[1].show_type, ShowType::Film);
+}
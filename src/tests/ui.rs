@@ -0,0 +1,96 @@
+use crate::{ui, Show};
+use watchlist::{
+    model::{Schedule, ShowRecord, ShowType, Status},
+    sites::{ImportError, ImportedShow, SearchResult},
+};
+
+#[test]
+fn show_record_converts_to_ui_show_and_back() {
+    let record = ShowRecord {
+        id: 12,
+        title: "Sousou no Frieren".to_owned(),
+        alternative_title: "Frieren: Beyond Journey's End".to_owned(),
+        release_date: "2023".to_owned(),
+        about: "An elf mage outlives her party.".to_owned(),
+        link_to_show: "https://www.imdb.com/title/tt22248376/".to_owned(),
+        link_to_picture: "/tmp/frieren.png".to_owned(),
+        score: 10,
+        favorite: true,
+        status: Status::Watching,
+        show_type: ShowType::Anime,
+        season: 1,
+        episodes_count: 28,
+        episode: 5,
+        schedule: Schedule {
+            release_time: "2023-09-29 18:00".to_owned(),
+            episodes_per_weekday: [0, 0, 0, 0, 1, 0, 0],
+        },
+    };
+
+    let show = Show::from(&record);
+    assert_eq!(show.title.as_str(), "Sousou no Frieren");
+    assert_eq!(show.schedule_friday, 1);
+    assert!(show.new_episodes_available);
+    assert_eq!(ShowRecord::from(&show), record);
+}
+
+#[test]
+fn import_result_tells_what_was_not_found() {
+    let record = ShowRecord {
+        title: "Perfect Blue".to_owned(),
+        about: "A pop singer becomes an actress.".to_owned(),
+        link_to_picture: "https://example.com/perfect_blue.jpg".to_owned(),
+        show_type: ShowType::Film,
+        ..Default::default()
+    };
+    let imported = ImportedShow {
+        runtime: Some(81),
+        ..record.into()
+    };
+    let result = ui::import_result(Ok(imported));
+    assert!(!result.failed);
+    assert_eq!(result.show.title.as_str(), "Perfect Blue");
+    assert_eq!(result.details.as_str(), "81 min");
+    assert_eq!(
+        result.message.as_str(),
+        "Not found on the page: release date, episodes count, schedule"
+    );
+
+    let error = ImportError::Network("Could not resolve host: example.com".to_owned());
+    let result = ui::import_result(Err(error));
+    assert!(result.failed);
+    assert_eq!(result.show.title.as_str(), "");
+    assert_eq!(
+        result.message.as_str(),
+        "Could not connect: Could not resolve host: example.com"
+    );
+}
+
+#[test]
+fn search_items_describe_each_result() {
+    let results = [
+        SearchResult {
+            title: "The Orville".to_owned(),
+            year: "2017-2022".to_owned(),
+            show_type: ShowType::Serial,
+            link: "https://www.imdb.com/title/tt5691552/".to_owned(),
+            site: "IMDb",
+            ..Default::default()
+        },
+        SearchResult {
+            title: "Kaijuu 8-gou".to_owned(),
+            show_type: ShowType::Anime,
+            link: "https://anilist.co/anime/178754".to_owned(),
+            site: "AniList",
+            ..Default::default()
+        },
+    ];
+    let items = ui::search_items(&results, vec![None, None]);
+    assert_eq!(items[0].title.as_str(), "The Orville");
+    assert_eq!(items[0].details.as_str(), "2017-2022 · serial · IMDb");
+    assert_eq!(
+        items[0].link.as_str(),
+        "https://www.imdb.com/title/tt5691552/"
+    );
+    assert_eq!(items[1].details.as_str(), "anime · AniList");
+}
@@ -0,0 +1,355 @@
+use std::{
+    rc::Rc,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::{AppWindow, ImportItem, ImportResult, SearchItem, Show, ShowType, Status};
+use anyhow::{Context, Result};
+use slint::{ComponentHandle, Model, ModelRc, Rgba8Pixel, SharedPixelBuffer, VecModel};
+use watchlist::{
+    database::Repository,
+    http,
+    import::{Change, PreviewItem, Resolution},
+    model::{self, Schedule, ShowRecord},
+    refresh::RefreshItem,
+    sites::{self, ImportError, ImportedShow, SearchResult},
+};
+
+impl From<model::Status> for Status {
+    fn from(status: model::Status) -> Self {
+        match status {
+            model::Status::WatchLater => Status::WatchLater,
+            model::Status::Watching => Status::Watching,
+            model::Status::Completed => Status::Completed,
+            model::Status::Dropped => Status::Dropped,
+        }
+    }
+}
+
+impl From<Status> for model::Status {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::WatchLater => model::Status::WatchLater,
+            Status::Watching => model::Status::Watching,
+            Status::Completed => model::Status::Completed,
+            Status::Dropped => model::Status::Dropped,
+        }
+    }
+}
+
+impl From<model::ShowType> for ShowType {
+    fn from(show_type: model::ShowType) -> Self {
+        match show_type {
+            model::ShowType::Serial => ShowType::Serial,
+            model::ShowType::Film => ShowType::Film,
+            model::ShowType::Cartoon => ShowType::Cartoon,
+            model::ShowType::Anime => ShowType::Anime,
+        }
+    }
+}
+
+impl From<ShowType> for model::ShowType {
+    fn from(show_type: ShowType) -> Self {
+        match show_type {
+            ShowType::Serial => model::ShowType::Serial,
+            ShowType::Film => model::ShowType::Film,
+            ShowType::Cartoon => model::ShowType::Cartoon,
+            ShowType::Anime => model::ShowType::Anime,
+        }
+    }
+}
+
+impl From<&ShowRecord> for Show {
+    fn from(s: &ShowRecord) -> Self {
+        let schedule = &s.schedule.episodes_per_weekday;
+        Show {
+            id: s.id as i32,
+            title: s.title.as_str().into(),
+            alternative_title: s.alternative_title.as_str().into(),
+            release_date: s.release_date.as_str().into(),
+            about: s.about.as_str().into(),
+            link_to_show: s.link_to_show.as_str().into(),
+            link_to_picture: s.link_to_picture.as_str().into(),
+            score: s.score,
+            favorite: s.favorite,
+            status: s.status.into(),
+            show_type: s.show_type.into(),
+            season: s.season,
+            episodes_count: s.episodes_count,
+            episode: s.episode,
+            release_time: s.schedule.release_time.as_str().into(),
+            schedule_monday: schedule[0] as i32,
+            schedule_tuesday: schedule[1] as i32,
+            schedule_wednesday: schedule[2] as i32,
+            schedule_thursday: schedule[3] as i32,
+            schedule_friday: schedule[4] as i32,
+            schedule_saturday: schedule[5] as i32,
+            schedule_sunday: schedule[6] as i32,
+            new_episodes_available: s.new_episodes_available(),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Show> for ShowRecord {
+    fn from(s: &Show) -> Self {
+        ShowRecord {
+            id: s.id as i64,
+            title: s.title.to_string(),
+            alternative_title: s.alternative_title.to_string(),
+            release_date: s.release_date.to_string(),
+            about: s.about.to_string(),
+            link_to_show: s.link_to_show.to_string(),
+            link_to_picture: s.link_to_picture.to_string(),
+            score: s.score,
+            favorite: s.favorite,
+            status: s.status.into(),
+            show_type: s.show_type.into(),
+            season: s.season,
+            episodes_count: s.episodes_count,
+            episode: s.episode,
+            schedule: Schedule {
+                release_time: s.release_time.to_string(),
+                episodes_per_weekday: [
+                    s.schedule_monday as u32,
+                    s.schedule_tuesday as u32,
+                    s.schedule_wednesday as u32,
+                    s.schedule_thursday as u32,
+                    s.schedule_friday as u32,
+                    s.schedule_saturday as u32,
+                    s.schedule_sunday as u32,
+                ],
+            },
+        }
+    }
+}
+
+pub fn import_item(item: &PreviewItem) -> ImportItem {
+    let details = match item.change {
+        Change::Changed => format!("Changes {}", item.changed_fields().join(", ")),
+        Change::Conflict => format!("Differs in {}", item.conflicts.join(", ")),
+        Change::New | Change::Unchanged => String::new(),
+    };
+    ImportItem {
+        title: item.incoming.show.title.as_str().into(),
+        change: item.change.to_string().into(),
+        details: details.into(),
+        conflict: item.change == Change::Conflict,
+        resolution: Resolution::ALL
+            .iter()
+            .position(|r| *r == item.resolution)
+            .unwrap_or_default() as i32,
+    }
+}
+
+/// A refreshed show in the import window, listing its changes one per line.
+pub fn refresh_item(item: &RefreshItem) -> ImportItem {
+    let (change, details) = match &item.fetched {
+        Err(e) => ("failed", e.to_string()),
+        Ok(_) => {
+            let changes = item.changes.iter().map(|c| c.to_string());
+            let change = match item.result() {
+                Some(_) => "changed",
+                None => "unchanged",
+            };
+            (change, changes.collect::<Vec<_>>().join("\n"))
+        }
+    };
+    ImportItem {
+        title: item.local.title.as_str().into(),
+        change: change.into(),
+        details: details.into(),
+        conflict: false,
+        resolution: 0,
+    }
+}
+
+/// The imported show with the fields its page lacked, or why it could not be imported.
+pub fn import_result(result: Result<ImportedShow, ImportError>) -> ImportResult {
+    match result {
+        Ok(imported) => {
+            let (_, missing) = sites::fields(&imported.show);
+            let message = match missing.is_empty() {
+                true => String::new(),
+                false => format!("Not found on the page: {}", missing.join(", ")),
+            };
+            ImportResult {
+                show: Show::from(&imported.show),
+                details: imported.details().into(),
+                failed: false,
+                message: message.into(),
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ImportResult {
+                show: Show::default(),
+                details: Default::default(),
+                failed: true,
+                message: e.to_string().into(),
+            }
+        }
+    }
+}
+
+/// The posters of the search results, downloaded all at once. Runs off the UI thread.
+pub fn search_pictures(
+    results: &[SearchResult],
+    mut progress: impl FnMut(usize),
+) -> Vec<Option<SharedPixelBuffer<Rgba8Pixel>>> {
+    thread::scope(|scope| {
+        let downloads = results
+            .iter()
+            .map(|result| {
+                scope.spawn(move || {
+                    if result.picture.is_empty() {
+                        return None;
+                    }
+                    http::get_bytes(&result.picture)
+                        .and_then(|content| decode_picture(&content))
+                        .map_err(|e| eprintln!("Error: {}", e))
+                        .ok()
+                })
+            })
+            .collect::<Vec<_>>();
+        downloads
+            .into_iter()
+            .enumerate()
+            .map(|(index, download)| {
+                let picture = download.join().ok().flatten();
+                progress(index + 1);
+                picture
+            })
+            .collect()
+    })
+}
+
+/// The search results to pick from, with the posters from `search_pictures`.
+pub fn search_items(
+    results: &[SearchResult],
+    pictures: Vec<Option<SharedPixelBuffer<Rgba8Pixel>>>,
+) -> Vec<SearchItem> {
+    results
+        .iter()
+        .zip(pictures)
+        .map(|(result, picture)| {
+            let details = [
+                result.year.clone(),
+                result.show_type.to_string(),
+                result.site.to_owned(),
+            ];
+            SearchItem {
+                title: result.title.as_str().into(),
+                details: details
+                    .into_iter()
+                    .filter(|detail| !detail.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" · ")
+                    .into(),
+                link: result.link.as_str().into(),
+                picture: picture.map(slint::Image::from_rgba8).unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Covers the window with the loading screen while work is done on another thread.
+pub fn show_busy(ui: &AppWindow, text: &str) {
+    ui.set_loading_text(text.into());
+    ui.set_loading_progress(0);
+}
+
+/// Takes the loading screen away, leaving it as it is for loading the watchlist.
+pub fn hide_busy(ui: &AppWindow) {
+    ui.set_loading_progress(100);
+    ui.set_loading_text("Loading...".into());
+}
+
+/// Shows from another thread that `done` of `count` steps are finished. The loading screen stays
+/// until the results are shown.
+pub fn report_progress(ui: &slint::Weak<AppWindow>, done: usize, count: usize) {
+    let progress = (done * 100 / count.max(1)).min(99) as i32;
+    let ui = ui.clone();
+    _ = slint::invoke_from_event_loop(move || {
+        if let Some(app) = ui.upgrade() {
+            app.set_loading_progress(progress);
+        }
+    });
+}
+
+pub fn decode_picture(content: &[u8]) -> Result<SharedPixelBuffer<Rgba8Pixel>> {
+    let picture = image::load_from_memory(content)
+        .context("Failed to load picture from memory")?
+        .into_rgba8();
+    Ok(SharedPixelBuffer::<Rgba8Pixel>::clone_from_slice(
+        picture.as_raw(),
+        picture.width(),
+        picture.height(),
+    ))
+}
+
+pub fn load_watchlist(
+    ui: &AppWindow,
+    repository: &Repository,
+    is_watchlist_loaded: Arc<Mutex<bool>>,
+) -> Result<()> {
+    let shows = repository
+        .load_shows()?
+        .iter()
+        .enumerate()
+        .map(|(index, record)| Show {
+            index: index as i32,
+            ..record.into()
+        })
+        .collect::<Vec<Show>>();
+    ui.invoke_set_shows(ModelRc::from(Rc::new(VecModel::from(shows))));
+
+    // Thumbnails are read on this thread, which owns the connection, and decoded in background.
+    let thumbnails = repository.load_thumbnails()?;
+    let ui_weak = ui.as_weak();
+    thread::spawn(move || {
+        decode_thumbnails(ui_weak, thumbnails);
+        let mut is_watchlist_loaded = is_watchlist_loaded.lock().unwrap();
+        *is_watchlist_loaded = true;
+    });
+    Ok(())
+}
+
+fn decode_thumbnails(ui: slint::Weak<AppWindow>, thumbnails: Vec<Option<Vec<u8>>>) {
+    let rows_number = thumbnails.len();
+    let model = Arc::new(Mutex::new(Vec::new()));
+
+    for (index, thumbnail) in thumbnails.into_iter().enumerate() {
+        let buffer = thumbnail.and_then(|content| {
+            decode_picture(&content)
+                .map_err(|e| eprintln!("Error: {}", e))
+                .ok()
+        });
+        model.lock().unwrap().push(buffer);
+
+        let loading_progress = (index + 1) as f32 / rows_number as f32 * 100.0;
+        let ui_clone = ui.clone();
+        _ = slint::invoke_from_event_loop(move || {
+            if let Some(app) = ui_clone.upgrade() {
+                app.set_loading_progress(loading_progress as i32);
+            }
+        });
+    }
+
+    _ = slint::invoke_from_event_loop(move || {
+        let model = model.lock().unwrap();
+        if let Some(app) = ui.upgrade() {
+            let shows = app.get_shows();
+            for i in 0..shows.row_count() {
+                if let Some(buffer) = model.get(i).cloned().flatten() {
+                    let picture = slint::Image::from_rgba8(buffer);
+                    let mut s = shows.row_data(i).unwrap();
+                    s.picture = picture;
+                    shows.set_row_data(i, s);
+                }
+            }
+            app.set_shows(shows);
+        }
+    });
+}